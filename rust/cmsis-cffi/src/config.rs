@@ -76,7 +76,12 @@ pub fn read_vidx_list(vidx_list: &Path) -> Vec<String> {
                     log::error!("Could not get parent directory for vendors.list");
                 }
             }
-            match OpenOptions::new().create(true).write(true).open(vidx_list) {
+            match OpenOptions::new()
+                .create(true)
+                .write(true)
+                .truncate(true)
+                .open(vidx_list)
+            {
                 Ok(mut fd) => {
                     let lines = new_content.join("\n");
                     fd.write_all(lines.as_bytes()).unwrap_or_else(|e| {
@@ -12,7 +12,11 @@ use crate::pack_index::{DownloadSender, RunningUpdateContext, UpdatePoll, Update
 use crate::pdsc::ParsedPacks;
 
 cffi! {
-    fn update_packs(
+    /// # Safety
+    ///
+    /// `pack_store` must be null or a NUL terminated string, and `parsed_packs` null or
+    /// a pointer returned by `parse_packs` that has not been freed.
+    unsafe fn update_packs(
         pack_store: *const c_char,
         parsed_packs: *mut ParsedPacks
     ) -> Result<*mut UpdatePoll> {
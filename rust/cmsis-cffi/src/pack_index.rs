@@ -82,7 +82,10 @@ impl UpdateReturn {
 }
 
 cffi! {
-    fn update_pdsc_index(
+    /// # Safety
+    ///
+    /// `pack_store` and `vidx_list` must each be null or a NUL terminated string.
+    unsafe fn update_pdsc_index(
         pack_store: *const c_char,
         vidx_list: *const c_char,
     ) -> Result<*mut UpdatePoll> {
@@ -172,7 +175,11 @@ pub unsafe extern "C" fn update_pdsc_get_status(ptr: *mut UpdatePoll) -> *mut Do
 }
 
 cffi! {
-    fn update_pdsc_status_free(ptr: *mut DownloadUpdate) {
+    /// # Safety
+    ///
+    /// `ptr` must be null or a pointer returned by `update_pdsc_get_status` that has not
+    /// been freed.
+    unsafe fn update_pdsc_status_free(ptr: *mut DownloadUpdate) {
         if !ptr.is_null() {
             drop(unsafe { Box::from_raw(ptr) })
         }
@@ -210,7 +217,10 @@ pub extern "C" fn update_pdsc_index_new() -> *mut UpdateReturn {
 }
 
 cffi! {
-    fn update_pdsc_index_next(ptr: *mut UpdateReturn) -> Result<*const c_char> {
+    /// # Safety
+    ///
+    /// `ptr` must be null or a live pointer to an `UpdateReturn` returned by this library.
+    unsafe fn update_pdsc_index_next(ptr: *mut UpdateReturn) -> Result<*const c_char> {
         if !ptr.is_null() {
             with_from_raw!(let mut boxed = ptr, {
                 if let Some(osstr) = boxed.0.pop().map(|p| p.into_os_string()){
@@ -231,7 +241,11 @@ cffi! {
 }
 
 cffi! {
-    fn update_pdsc_index_push(ptr: *mut UpdateReturn, cstr: *mut c_char) -> Result<()> {
+    /// # Safety
+    ///
+    /// `ptr` must be null or a live pointer to an `UpdateReturn` returned by this library,
+    /// and `cstr` null or a NUL terminated string.
+    unsafe fn update_pdsc_index_push(ptr: *mut UpdateReturn, cstr: *mut c_char) -> Result<()> {
         if !ptr.is_null() && !cstr.is_null() {
             with_from_raw!(let mut boxed = ptr, {
                 let pstore = unsafe { CStr::from_ptr(cstr) }.to_string_lossy();
@@ -245,7 +259,10 @@ cffi! {
 }
 
 cffi! {
-    fn cstring_free(ptr: *mut c_char) {
+    /// # Safety
+    ///
+    /// `ptr` must be null or a string returned by this library that has not been freed.
+    unsafe fn cstring_free(ptr: *mut c_char) {
         if !ptr.is_null() {
             drop(unsafe { CString::from_raw(ptr) })
         }
@@ -253,7 +270,11 @@ cffi! {
 }
 
 cffi! {
-    fn update_pdsc_index_free(ptr: *mut UpdateReturn) {
+    /// # Safety
+    ///
+    /// `ptr` must be null or a pointer to an `UpdateReturn` returned by this library that
+    /// has not been freed.
+    unsafe fn update_pdsc_index_free(ptr: *mut UpdateReturn) {
         if !ptr.is_null() {
             drop(unsafe { Box::from_raw(ptr) })
         }
@@ -3,14 +3,18 @@ use std::ffi::{CStr, CString};
 use std::os::raw::c_char;
 use std::path::{Path, PathBuf};
 
-use cmsis_pack::pdsc::{dump_devices, Package};
+use cmsis_pack::pdsc::{dump_devices, Pack};
 use cmsis_pack::utils::FromElem;
 use cmsis_pack::utils::ResultLogExt;
 
 use crate::pack_index::UpdateReturn;
 
 cffi! {
-    fn dump_pdsc_json(
+    /// # Safety
+    ///
+    /// `packs` must be a live pointer returned by `parse_packs`, and `devices_dest` and
+    /// `boards_dest` each null or a NUL terminated string.
+    unsafe fn dump_pdsc_json(
         packs: *mut ParsedPacks,
         devices_dest: *const c_char,
         boards_dest: *const c_char,
@@ -36,16 +40,19 @@ cffi! {
     }
 }
 
-pub struct ParsedPacks(pub(crate) Vec<Package>);
+pub struct ParsedPacks(pub(crate) Vec<Pack>);
 
 impl ParsedPacks {
-    pub fn iter(&self) -> impl Iterator<Item = &Package> {
+    pub fn iter(&self) -> impl Iterator<Item = &Pack> {
         self.0.iter()
     }
 }
 
 cffi! {
-    fn pack_from_path(ptr: *const c_char) -> Result<*mut UpdateReturn>{
+    /// # Safety
+    ///
+    /// `ptr` must be null or a NUL terminated string.
+    unsafe fn pack_from_path(ptr: *const c_char) -> Result<*mut UpdateReturn>{
         if !ptr.is_null() {
             let fname = unsafe { CStr::from_ptr(ptr) }.to_string_lossy();
             let mut pathbuf = PathBuf::new();
@@ -62,13 +69,16 @@ cffi! {
 }
 
 cffi! {
-    fn parse_packs(ptr: *mut UpdateReturn) -> Result<*mut ParsedPacks>{
+    /// # Safety
+    ///
+    /// `ptr` must be null or a live pointer to an `UpdateReturn` returned by this library.
+    unsafe fn parse_packs(ptr: *mut UpdateReturn) -> Result<*mut ParsedPacks>{
         if !ptr.is_null() {
             with_from_raw!(let boxed = ptr,{
                 let pdsc_files = boxed.iter();
                 Ok(Box::into_raw(Box::new(ParsedPacks(
                     pdsc_files
-                        .filter_map(|input| Package::from_path(Path::new(input)).ok_warn())
+                        .filter_map(|input| Pack::from_path(Path::new(input)).ok_warn())
                         .collect()))))
             })
         } else {
@@ -78,7 +88,10 @@ cffi! {
 }
 
 cffi! {
-    fn parse_packs_free(ptr: *mut ParsedPacks) {
+    /// # Safety
+    ///
+    /// `ptr` must be null or a pointer returned by `parse_packs` that has not been freed.
+    unsafe fn parse_packs_free(ptr: *mut ParsedPacks) {
         if !ptr.is_null() {
             drop(unsafe { Box::from_raw(ptr) })
         }
@@ -86,7 +99,10 @@ cffi! {
 }
 
 cffi! {
-    fn dumps_components(ptr: *mut ParsedPacks) -> Result<*const c_char> {
+    /// # Safety
+    ///
+    /// `ptr` must be a live pointer returned by `parse_packs`.
+    unsafe fn dumps_components(ptr: *mut ParsedPacks) -> Result<*const c_char> {
         with_from_raw!(let boxed = ptr, {
             let pdscs = boxed.iter();
             let dumped_components = cmsis_pack::pdsc::dumps_components(pdscs)?;
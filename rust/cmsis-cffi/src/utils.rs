@@ -10,7 +10,7 @@ use std::thread;
 use anyhow::Error;
 
 thread_local! {
-    pub static LAST_ERROR: RefCell<Option<Error>> = RefCell::new(None);
+    pub static LAST_ERROR: RefCell<Option<Error>> = const { RefCell::new(None) };
 }
 
 pub(crate) fn set_last_error(err: Error) {
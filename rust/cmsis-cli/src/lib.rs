@@ -9,7 +9,7 @@ use std::path::Path;
 use std::sync::{Arc, Mutex};
 
 extern crate cmsis_pack;
-use cmsis_pack::pdsc::{dump_devices, Component, FileRef, Package};
+use cmsis_pack::pdsc::{dump_devices, Component, FileRef, Pack};
 use cmsis_pack::update::{install, update, DownloadProgress};
 use cmsis_pack::utils::FromElem;
 
@@ -66,7 +66,7 @@ pub fn install_command(conf: &Config, args: &ArgMatches<'_>) -> Result<(), Error
     let pdsc_list: Vec<_> = args
         .values_of("PDSC")
         .unwrap()
-        .filter_map(|input| Package::from_path(Path::new(input)).ok())
+        .filter_map(|input| Pack::from_path(Path::new(input)).ok())
         .collect();
     let progress = CliProgress::new();
     let updated = install(conf, pdsc_list.iter(), progress)?;
@@ -150,14 +150,14 @@ pub fn dump_devices_command(c: &Config, args: &ArgMatches<'_>) -> Result<(), Err
         .unwrap();
     let pdscs = filenames
         .into_iter()
-        .flat_map(|filename| match Package::from_path(&filename) {
+        .flat_map(|filename| match Pack::from_path(&filename) {
             Ok(c) => Some(c),
             Err(e) => {
                 log::error!("parsing {:?}: {}", filename, e);
                 None
             }
         })
-        .collect::<Vec<Package>>();
+        .collect::<Vec<Pack>>();
     let to_ret = dump_devices(&pdscs, args.value_of("devices"), args.value_of("boards"));
     log::debug!("exiting");
     to_ret
@@ -177,7 +177,7 @@ pub fn check_args<'a, 'b>() -> App<'a, 'b> {
 
 pub fn check_command(_: &Config, args: &ArgMatches<'_>) -> Result<(), Error> {
     let filename = args.value_of("INPUT").unwrap();
-    match Package::from_path(Path::new(filename)) {
+    match Pack::from_path(Path::new(filename)) {
         Ok(c) => {
             log::info!("Parsing succedded");
             log::info!("{} Valid Conditions", c.conditions.0.len());
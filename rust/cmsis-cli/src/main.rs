@@ -1,6 +1,5 @@
 extern crate clap;
 
-use anyhow::Error;
 use clap::{App, Arg};
 use cmsis_cli::{
     check_args, check_command, dump_devices_args, dump_devices_command, install_args,
@@ -33,25 +32,21 @@ fn main() {
     match matches.subcommand() {
         ("update", Some(sub_m)) => {
             Config::new()
-                .map_err(Error::from)
                 .and_then(|config| update_command(&config, sub_m))
                 .unwrap();
         }
         ("install", Some(sub_m)) => {
             Config::new()
-                .map_err(Error::from)
                 .and_then(|config| install_command(&config, sub_m))
                 .unwrap();
         }
         ("check", Some(sub_m)) => {
             Config::new()
-                .map_err(Error::from)
                 .and_then(|config| check_command(&config, sub_m))
                 .unwrap();
         }
         ("dump-devices", Some(sub_m)) => {
             Config::new()
-                .map_err(Error::from)
                 .and_then(|config| dump_devices_command(&config, sub_m))
                 .unwrap();
         }
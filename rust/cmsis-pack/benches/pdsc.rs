@@ -0,0 +1,21 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use cmsis_pack::pdsc::Devices;
+use cmsis_pack::utils::FromElem;
+
+const FAMILY: &str = include_str!("bench_family.pdsc");
+
+fn parse_family(c: &mut Criterion) {
+    c.bench_function("parse multi-core family", |b| {
+        b.iter(|| Devices::from_string(black_box(FAMILY)).unwrap())
+    });
+}
+
+fn list_family(c: &mut Criterion) {
+    c.bench_function("list multi-core family", |b| {
+        b.iter(|| Devices::list_names(black_box(FAMILY)).unwrap())
+    });
+}
+
+criterion_group!(benches, parse_family, list_family);
+criterion_main!(benches);
@@ -0,0 +1,73 @@
+//! Report the peak resident set size of parsing every PDSC in a directory.
+//!
+//! Run once per loader and compare, e.g. over an unpacked DFP directory:
+//!
+//! ```text
+//! cargo run --release --features mmap --example peak_rss -- string ~/.packs
+//! cargo run --release --features mmap --example peak_rss -- mmap ~/.packs
+//! ```
+//!
+//! The peak is read from `VmHWM` in `/proc/self/status`, so this only reports it on Linux.
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use cmsis_pack::pdsc::Devices;
+use cmsis_pack::utils::FromElem;
+
+fn pdscs(dir: &Path, found: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for path in entries.filter_map(Result::ok).map(|e| e.path()) {
+        if path.is_dir() {
+            pdscs(&path, found);
+        } else if path.extension().is_some_and(|ext| ext == "pdsc") {
+            found.push(path);
+        }
+    }
+}
+
+fn peak_rss() -> Option<String> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmHWM:"))
+        .map(|peak| peak.trim().to_string())
+}
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let (Some(mode), Some(dir)) = (args.next(), args.next()) else {
+        eprintln!("usage: peak_rss <string|mmap> <directory>");
+        std::process::exit(2);
+    };
+    let mut files = Vec::new();
+    pdscs(Path::new(&dir), &mut files);
+
+    let mut devices = 0;
+    for file in &files {
+        let parsed = match mode.as_str() {
+            "string" => fs::read_to_string(file)
+                .map_err(Into::into)
+                .and_then(|s| Devices::from_string(&s)),
+            "mmap" => Devices::from_mmap(file),
+            _ => {
+                eprintln!("unknown loader {}, expected string or mmap", mode);
+                std::process::exit(2);
+            }
+        };
+        match parsed {
+            Ok(parsed) => devices += parsed.0.len(),
+            Err(e) => eprintln!("{}: {}", file.display(), e),
+        }
+    }
+
+    println!(
+        "{}: {} devices from {} files, peak RSS {}",
+        mode,
+        devices,
+        files.len(),
+        peak_rss().as_deref().unwrap_or("unknown")
+    );
+}
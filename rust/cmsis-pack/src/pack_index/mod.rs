@@ -119,7 +119,7 @@ mod test {
     #[test]
     fn pdscref_optionals() {
         let good_string =
-            "<pdsc vendor=\"Vendor\" url=\"Url\" name=\"Name\" version=\"1.2.3-alpha\">";
+            "<pdsc vendor=\"Vendor\" url=\"Url\" name=\"Name\" version=\"1.2.3-alpha\"/>";
         let response = PdscRef::from_string(good_string).unwrap();
         assert_eq!(response.vendor, String::from("Vendor"));
         assert_eq!(response.url, "Url");
@@ -127,7 +127,7 @@ mod test {
         assert_eq!(response.version, String::from("1.2.3-alpha"));
         let good_string =
             "<pdsc vendor=\"Vendor\" url=\"Url\" name=\"Name\" version=\"1.2.3-alpha\"
-                date=\"A-Date\" deprecated=\"true\" replacement=\"Other\" size=\"8MB\">";
+                date=\"A-Date\" deprecated=\"true\" replacement=\"Other\" size=\"8MB\"/>";
         let response = PdscRef::from_string(good_string).unwrap();
         assert_eq!(response.date, Some(String::from("A-Date")));
         assert_eq!(response.deprecated, Some(String::from("true")));
@@ -217,7 +217,7 @@ mod test {
         assert_eq!(response.url, "Url");
         assert_eq!(
             response.timestamp,
-            Some(String::from("Fri Sep  1 13:26:41 CDT"))
+            Some(String::from("Fri Sep  1 13:26:41 CDT 2017"))
         );
     }
 }
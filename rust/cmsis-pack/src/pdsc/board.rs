@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+
+use anyhow::Error;
+use roxmltree::Node;
+use serde::{Deserialize, Serialize};
+
+use super::device::strip_vendor_id;
+use super::{Device, Devices, Memories};
+use crate::utils::prelude::*;
+use crate::utils::Diagnostic;
+
+/// An evaluation or development `<board>` and the devices mounted on it.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Board {
+    pub name: String,
+    /// The maker of the board, which for third-party evaluation boards differs from the vendor
+    /// of the devices it carries.
+    pub vendor: Option<String>,
+    #[serde(default)]
+    pub revision: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// The `Dname` of each `<mountedDevice>`, which is its key in [`Devices`].
+    pub mounted_devices: Vec<String>,
+    /// The `Dvendor` of each of the `mounted_devices`, without its `:id` suffix.
+    #[serde(default)]
+    pub mounted_device_vendors: Vec<Option<String>>,
+    pub debug_interface: Option<String>,
+    /// Memory the board adds to its mounted devices, such as external SDRAM. These are kept
+    /// apart from the on-chip [`Device::memories`].
+    #[serde(default)]
+    pub extra_memories: Memories,
+}
+
+impl Board {
+    /// The mounted devices of this board that are present in `devices`.
+    pub fn devices<'a>(&'a self, devices: &'a Devices) -> impl Iterator<Item = &'a Device> {
+        self.mounted_devices
+            .iter()
+            .filter_map(move |name| devices.0.get(name))
+    }
+
+    /// Returns true if the device `dname` is mounted on this board.
+    pub fn carries(&self, dname: &str) -> bool {
+        self.mounted_devices.iter().any(|name| name == dname)
+    }
+}
+
+impl FromElem for Board {
+    fn from_elem(e: &Node) -> Result<Self, Error> {
+        Self::from_elem_with(e, &ParseOptions::default())
+    }
+
+    fn from_elem_with(e: &Node, opts: &ParseOptions) -> Result<Self, Error> {
+        Self::from_elem_with_diagnostics(e, opts, &mut LogDiagnostics)
+    }
+
+    fn from_elem_with_diagnostics(
+        e: &Node,
+        _opts: &ParseOptions,
+        diags: &mut dyn Diagnostics,
+    ) -> Result<Self, Error> {
+        assert_root_name(e, "board")?;
+        let (mounted_devices, mounted_device_vendors) = e
+            .children()
+            .filter(|c| c.tag_name().name() == "mountedDevice")
+            .filter_map(|c| {
+                let name: String = attr_map(&c, "Dname").ok()?;
+                let vendor = c
+                    .attribute("Dvendor")
+                    .map(|v| strip_vendor_id(v).to_string());
+                Some((name, vendor))
+            })
+            .unzip();
+        Ok(Self {
+            name: attr_map(e, "name")?,
+            vendor: attr_map(e, "vendor").ok(),
+            revision: attr_map(e, "revision").ok(),
+            description: child_text(e, "description")
+                .ok()
+                .map(|text| text.trim().to_string()),
+            mounted_devices,
+            mounted_device_vendors,
+            debug_interface: e
+                .children()
+                .find(|c| c.tag_name().name() == "debugInterface")
+                .and_then(|c| attr_map(&c, "adapter").ok()),
+            extra_memories: Memories::from_children(e, diags),
+        })
+    }
+}
+
+/// The `<boards>` of a pack, by name.
+#[derive(Debug, Default, Serialize)]
+pub struct Boards(pub HashMap<String, Board>);
+
+impl Boards {
+    /// The boards carrying the device `dname`, sorted by name.
+    pub fn for_device(&self, dname: &str) -> Vec<&Board> {
+        let mut boards: Vec<&Board> = self.0.values().filter(|b| b.carries(dname)).collect();
+        boards.sort_by(|a, b| a.name.cmp(&b.name));
+        boards
+    }
+}
+
+impl FromElem for Boards {
+    fn from_elem(e: &Node) -> Result<Self, Error> {
+        Self::from_elem_with(e, &ParseOptions::default())
+    }
+
+    fn from_elem_with(e: &Node, opts: &ParseOptions) -> Result<Self, Error> {
+        Self::from_elem_with_diagnostics(e, opts, &mut LogDiagnostics)
+    }
+
+    fn from_elem_with_diagnostics(
+        e: &Node,
+        opts: &ParseOptions,
+        diags: &mut dyn Diagnostics,
+    ) -> Result<Self, Error> {
+        assert_root_name(e, "boards")?;
+        let mut boards = HashMap::new();
+        for c in e.children().filter(|c| c.has_tag_name("board")) {
+            let Some(board) =
+                Board::from_elem_with_diagnostics(&c, opts, diags).ok_report(&c, diags)
+            else {
+                continue;
+            };
+            if let Some(dup) = boards.insert(board.name.clone(), board) {
+                diags.report(Diagnostic::new(
+                    &c,
+                    format!("Duplicate Board found {}", dup.name),
+                ));
+            }
+        }
+        Ok(Boards(boards))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn boards_sharing_a_device() {
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="STM32F4 Series" Dvendor="STMicroelectronics:13">
+                <processor Dcore="Cortex-M4"/>
+                <device Dname="STM32F407VG"/>
+                <device Dname="STM32F429ZI"/>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        let boards = Boards::from_string(
+            r#"<boards>
+              <board name="STM32F4-Discovery" vendor="STMicroelectronics" revision="Rev.C">
+                <description>
+                  STM32F4 Discovery kit
+                </description>
+                <mountedDevice deviceIndex="0" Dvendor="STMicroelectronics:13" Dname="STM32F407VG"/>
+              </board>
+              <board name="Arch Max" vendor="Seeed">
+                <mountedDevice deviceIndex="0" Dvendor="STMicroelectronics:13" Dname="STM32F407VG"/>
+              </board>
+              <board vendor="Nameless"/>
+            </boards>"#,
+        )
+        .unwrap();
+        assert_eq!(boards.0.len(), 2);
+        let discovery = &boards.0["STM32F4-Discovery"];
+        assert_eq!(discovery.revision.as_deref(), Some("Rev.C"));
+        assert_eq!(
+            discovery.description.as_deref(),
+            Some("STM32F4 Discovery kit")
+        );
+        assert_eq!(boards.0["Arch Max"].revision, None);
+        assert_eq!(boards.0["Arch Max"].description, None);
+
+        let names: Vec<&str> = boards
+            .for_device("STM32F407VG")
+            .into_iter()
+            .map(|b| b.name.as_str())
+            .collect();
+        assert_eq!(names, ["Arch Max", "STM32F4-Discovery"]);
+        assert!(boards.for_device("STM32F429ZI").is_empty());
+        for board in boards.0.values() {
+            assert!(board
+                .mounted_devices
+                .iter()
+                .all(|d| devices.0.contains_key(d)));
+            let mounted: Vec<&str> = board.devices(&devices).map(|d| d.name.as_str()).collect();
+            assert_eq!(mounted, ["STM32F407VG"]);
+        }
+    }
+}
@@ -1,28 +1,229 @@
+use std::collections::HashMap;
+
 use anyhow::Error;
 use roxmltree::Node;
 
+use super::device::strip_vendor_id;
+use super::{Core, Device, Endian, Processor, TrustZone, FPU, MPU};
 use crate::utils::prelude::*;
 
+/// The environment conditions are evaluated in, besides the device itself.
+#[derive(Debug, Clone, Default)]
+pub struct ConditionContext {
+    /// The toolchain in use, matched against `Tcompiler`, e.g. `GCC` or `ARMCC`. Conditions on
+    /// the compiler never match when it is not set.
+    pub compiler: Option<String>,
+}
+
+/// One `<require>`, `<accept>` or `<deny>` rule of a [`Condition`].
+#[derive(Debug, Clone)]
 pub struct ConditionComponent {
     pub device_family: Option<String>,
     pub device_sub_family: Option<String>,
     pub device_variant: Option<String>,
     pub device_vendor: Option<String>,
     pub device_name: Option<String>,
+    /// Matches a device with a processor of this core, compared with [`Core::matches`].
+    pub device_core: Option<Core>,
+    /// Matches a processor of this byte order, or one whose byte order is configurable.
+    pub device_endian: Option<Endian>,
+    /// Matches a processor with at least this FPU, so `FPU` and `SP_FPU` match any FPU.
+    pub device_fpu: Option<FPU>,
+    pub device_mpu: Option<MPU>,
+    pub device_tz: Option<TrustZone>,
+    /// Matches a processor of this `Pname`, ignoring ASCII case.
+    pub processor_name: Option<String>,
+    pub compiler: Option<String>,
+    /// The id of another condition that must also hold.
+    pub condition: Option<String>,
+    /// The device and processor attributes, `D*` and `P*`, that are not evaluated or whose
+    /// value could not be parsed. A rule with any of these never matches.
+    pub unsupported: Vec<String>,
+    /// Every attribute of the rule as written, including those not evaluated, such as
+    /// `Cclass`.
+    pub attributes: HashMap<String, String>,
+}
+
+/// The device and processor attributes [`ConditionComponent::matches`] evaluates.
+const EVALUATED: &[&str] = &[
+    "Dfamily",
+    "DsubFamily",
+    "Dvariant",
+    "Dvendor",
+    "Dname",
+    "Dcore",
+    "Dendian",
+    "Dfpu",
+    "Dmpu",
+    "Dtz",
+    "Pname",
+];
+
+/// Parses the attribute `name` of `e`, if present, with `parse`, adding `name` to
+/// `unsupported` when the value doesn't parse.
+fn parse_attr<T, F>(e: &Node, name: &str, unsupported: &mut Vec<String>, parse: F) -> Option<T>
+where
+    F: Fn(&str) -> Result<T, Error>,
+{
+    let parsed = parse(e.attribute(name)?).ok();
+    if parsed.is_none() {
+        unsupported.push(name.to_string());
+    }
+    parsed
 }
 
 impl FromElem for ConditionComponent {
     fn from_elem(e: &Node) -> Result<Self, Error> {
+        let mut unsupported: Vec<String> = e
+            .attributes()
+            .map(|a| a.name())
+            .filter(|name| name.starts_with(['D', 'P']) && !EVALUATED.contains(name))
+            .map(String::from)
+            .collect();
+        let device_core = parse_attr(e, "Dcore", &mut unsupported, str::parse);
+        let device_endian = parse_attr(e, "Dendian", &mut unsupported, str::parse);
+        let device_fpu = parse_attr(e, "Dfpu", &mut unsupported, |fpu| match fpu {
+            "NO_FPU" => Ok(FPU::None),
+            fpu => fpu.parse(),
+        });
+        let device_mpu = parse_attr(e, "Dmpu", &mut unsupported, |mpu| match mpu {
+            "NO_MPU" => Ok(MPU::NotPresent),
+            mpu => mpu.parse(),
+        });
+        let device_tz = parse_attr(e, "Dtz", &mut unsupported, str::parse);
+        unsupported.sort();
         Ok(ConditionComponent {
             device_family: attr_map(e, "Dfamily").ok(),
-            device_sub_family: attr_map(e, "Dsubfamily").ok(),
+            device_sub_family: attr_map(e, "DsubFamily").ok(),
             device_variant: attr_map(e, "Dvariant").ok(),
             device_vendor: attr_map(e, "Dvendor").ok(),
             device_name: attr_map(e, "Dname").ok(),
+            device_core,
+            device_endian,
+            device_fpu,
+            device_mpu,
+            device_tz,
+            processor_name: attr_map(e, "Pname").ok(),
+            compiler: attr_map(e, "Tcompiler").ok(),
+            condition: attr_map(e, "condition").ok(),
+            unsupported,
+            attributes: e
+                .attributes()
+                .map(|a| (a.name().to_string(), a.value().to_string()))
+                .collect(),
         })
     }
 }
 
+/// Matches `name` against a `Dname` pattern, where `*` matches any run of characters and `?`
+/// any single character.
+///
+/// Backtracks only to the most recent `*`, so hostile patterns can't blow up exponentially.
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = star {
+            p = star_p + 1;
+            n = star_n + 1;
+            star = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+impl ConditionComponent {
+    /// Returns true if every processor attribute of this element holds for `processor`.
+    fn matches_processor(&self, processor: &Processor) -> bool {
+        self.device_core
+            .as_ref()
+            .map_or(true, |core| processor.core.matches(core))
+            && self.device_endian.map_or(true, |endian| {
+                processor.endian == endian || processor.endian == Endian::Configurable
+            })
+            && self.device_fpu.as_ref().map_or(true, |fpu| match fpu {
+                FPU::None => !processor.fpu.is_present(),
+                FPU::SinglePrecision => processor.fpu.is_present(),
+                FPU::DoublePrecision => processor.fpu.is_double_precision(),
+            })
+            && self
+                .device_mpu
+                .as_ref()
+                .map_or(true, |mpu| processor.mpu == *mpu)
+            && self
+                .device_tz
+                .as_ref()
+                .map_or(true, |tz| processor.tz == *tz)
+            && self.processor_name.as_deref().map_or(true, |pname| {
+                processor
+                    .name
+                    .as_deref()
+                    .is_some_and(|name| name.eq_ignore_ascii_case(pname))
+            })
+    }
+
+    /// Returns true if every attribute of this element holds for `device` in `ctx`.
+    ///
+    /// The processor attributes must all hold for the same processor of `device`.
+    fn matches<'a>(
+        &'a self,
+        ctx: &ConditionContext,
+        device: &Device,
+        conditions: &dyn Fn(&str) -> Option<&'a Condition>,
+        visiting: &mut Vec<&'a str>,
+    ) -> bool {
+        let name_matches = |pattern: &str| {
+            wildcard_match(pattern, &device.name)
+                || device
+                    .base_device
+                    .as_deref()
+                    .is_some_and(|base| wildcard_match(pattern, base))
+        };
+        self.unsupported.is_empty()
+            && self.device_name.as_deref().map_or(true, name_matches)
+            && self.device_variant.as_deref().map_or(true, |variant| {
+                device.base_device.is_some() && wildcard_match(variant, &device.name)
+            })
+            && self
+                .device_family
+                .as_ref()
+                .map_or(true, |family| *family == device.family)
+            && self.device_sub_family.as_ref().map_or(true, |sub_family| {
+                Some(sub_family) == device.sub_family.as_ref()
+            })
+            && device.processors.iter().any(|p| self.matches_processor(p))
+            && self.device_vendor.as_deref().map_or(true, |vendor| {
+                Some(strip_vendor_id(vendor)) == device.vendor_name()
+            })
+            && self.compiler.as_deref().map_or(true, |compiler| {
+                ctx.compiler
+                    .as_deref()
+                    .is_some_and(|c| c.eq_ignore_ascii_case(compiler))
+            })
+            && self
+                .condition
+                .as_deref()
+                .map_or(true, |id| match conditions(id) {
+                    Some(condition) => condition.holds(ctx, device, conditions, visiting),
+                    None => {
+                        log::warn!("Unknown condition {}", id);
+                        false
+                    }
+                })
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct Condition {
     pub id: String,
     pub accept: Vec<ConditionComponent>,
@@ -39,13 +240,13 @@ impl FromElem for Condition {
         for elem in e.children().filter(|e| e.is_element()) {
             match elem.tag_name().name() {
                 "accept" => {
-                    accept.push(ConditionComponent::from_elem(e)?);
+                    accept.push(ConditionComponent::from_elem(&elem)?);
                 }
                 "deny" => {
-                    deny.push(ConditionComponent::from_elem(e)?);
+                    deny.push(ConditionComponent::from_elem(&elem)?);
                 }
                 "require" => {
-                    require.push(ConditionComponent::from_elem(e)?);
+                    require.push(ConditionComponent::from_elem(&elem)?);
                 }
                 "description" => {}
                 _ => {
@@ -65,17 +266,320 @@ impl FromElem for Condition {
     }
 }
 
-#[derive(Default)]
-pub struct Conditions(pub Vec<Condition>);
+impl Condition {
+    /// Evaluates this condition for `device` in `ctx`. Every `require` must match, at least one
+    /// `accept` must match if there are any, and no `deny` may match.
+    ///
+    /// `conditions` resolves the conditions referenced by this one, see
+    /// [`Pack::make_condition_lookup`](super::Pack::make_condition_lookup).
+    pub fn evaluate(
+        &self,
+        ctx: &ConditionContext,
+        device: &Device,
+        conditions: &HashMap<&str, &Condition>,
+    ) -> bool {
+        self.holds(
+            ctx,
+            device,
+            &|id| conditions.get(id).copied(),
+            &mut Vec::new(),
+        )
+    }
+
+    fn holds<'a>(
+        &'a self,
+        ctx: &ConditionContext,
+        device: &Device,
+        conditions: &dyn Fn(&str) -> Option<&'a Condition>,
+        visiting: &mut Vec<&'a str>,
+    ) -> bool {
+        if visiting.contains(&self.id.as_str()) {
+            log::warn!("Condition {} refers to itself", self.id);
+            return false;
+        }
+        visiting.push(&self.id);
+        let mut matches = |c: &'a ConditionComponent| c.matches(ctx, device, conditions, visiting);
+        let holds = self.require.iter().all(&mut matches)
+            && (self.accept.is_empty() || self.accept.iter().any(&mut matches))
+            && !self.deny.iter().any(&mut matches);
+        visiting.pop();
+        holds
+    }
+}
+
+/// The `<conditions>` of a pack, by id.
+#[derive(Debug, Clone, Default)]
+pub struct Conditions(pub HashMap<String, Condition>);
+
+impl Conditions {
+    /// The condition with `id`, if declared.
+    pub fn get(&self, id: &str) -> Option<&Condition> {
+        self.0.get(id)
+    }
+}
 
 impl FromElem for Conditions {
     fn from_elem(e: &Node) -> Result<Self, Error> {
         assert_root_name(e, "conditions")?;
-        Ok(Conditions(
-            e.children()
-                .filter(|e| e.is_element())
-                .flat_map(|c| Condition::from_elem(&c).ok_warn())
-                .collect(),
-        ))
+        let mut conditions = HashMap::new();
+        for condition in e
+            .children()
+            .filter(|e| e.is_element())
+            .flat_map(|c| Condition::from_elem(&c).ok_warn())
+        {
+            if let Some(dup) = conditions.insert(condition.id.clone(), condition) {
+                log::warn!("Duplicate Condition found {}", dup.id);
+            }
+        }
+        Ok(Conditions(conditions))
+    }
+}
+
+impl Device {
+    /// Returns true if the condition `condition_id` of `conditions` holds for this device in
+    /// `ctx`, such as the `condition` of one of its [memories](super::Memory) or
+    /// [algorithms](super::Algorithm).
+    ///
+    /// A condition that isn't declared never holds.
+    pub fn applies(
+        &self,
+        condition_id: &str,
+        conditions: &Conditions,
+        ctx: &ConditionContext,
+    ) -> bool {
+        match conditions.get(condition_id) {
+            Some(condition) => {
+                condition.holds(ctx, self, &|id| conditions.get(id), &mut Vec::new())
+            }
+            None => {
+                log::warn!("{}: unknown condition {}", self.name, condition_id);
+                false
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::pdsc::Devices;
+
+    const CONDITIONS: &str = r#"
+        <conditions>
+          <condition id="GCC">
+            <require Tcompiler="GCC"/>
+          </condition>
+          <condition id="STM32F4 GCC">
+            <require Dvendor="STMicroelectronics:13" Dname="STM32F4*"/>
+            <require condition="GCC"/>
+          </condition>
+          <condition id="Not F401">
+            <accept DsubFamily="STM32F401"/>
+            <accept DsubFamily="STM32F407"/>
+            <deny Dname="STM32F401?B"/>
+          </condition>
+          <condition id="Loop">
+            <require condition="Loop"/>
+          </condition>
+        </conditions>
+    "#;
+
+    fn devices() -> Devices {
+        Devices::from_string(
+            r#"<devices>
+              <family Dfamily="STM32F4" Dvendor="STMicroelectronics:13">
+                <processor Dcore="Cortex-M4"/>
+                <subFamily DsubFamily="STM32F401">
+                  <device Dname="STM32F401CB"/>
+                  <device Dname="STM32F401CC"/>
+                </subFamily>
+                <subFamily DsubFamily="STM32F411">
+                  <device Dname="STM32F411RE"/>
+                </subFamily>
+              </family>
+            </devices>"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn evaluate_conditions() {
+        let conditions = Conditions::from_string(CONDITIONS).unwrap();
+        let lookup: HashMap<&str, &Condition> =
+            conditions.0.values().map(|c| (c.id.as_str(), c)).collect();
+        let devices = devices();
+        let gcc = ConditionContext {
+            compiler: Some("gcc".into()),
+        };
+        let armcc = ConditionContext {
+            compiler: Some("ARMCC".into()),
+        };
+        let eval = |id: &str, ctx: &ConditionContext, device: &str| {
+            lookup[id].evaluate(ctx, &devices.0[device], &lookup)
+        };
+
+        assert!(eval("STM32F4 GCC", &gcc, "STM32F411RE"));
+        assert!(!eval("STM32F4 GCC", &armcc, "STM32F411RE"));
+        assert!(!eval("GCC", &ConditionContext::default(), "STM32F411RE"));
+
+        assert!(eval("Not F401", &gcc, "STM32F401CC"));
+        assert!(!eval("Not F401", &gcc, "STM32F401CB"));
+        assert!(!eval("Not F401", &gcc, "STM32F411RE"));
+
+        assert!(!eval("Loop", &gcc, "STM32F411RE"));
+    }
+
+    #[test]
+    fn conditions_by_id() {
+        let conditions = Conditions::from_string(
+            r#"<conditions>
+              <condition id="CM4 FPU">
+                <description>Cortex-M4 with FPU</description>
+                <require Dcore="Cortex-M4" Dfpu="FPU"/>
+                <deny Pname="cm7"/>
+              </condition>
+              <condition id="GCC">
+                <require Tcompiler="ARMCC"/>
+              </condition>
+              <condition id="GCC">
+                <require Tcompiler="GCC"/>
+              </condition>
+            </conditions>"#,
+        )
+        .unwrap();
+        assert_eq!(conditions.0.len(), 2);
+        let fpu = conditions.get("CM4 FPU").unwrap();
+        assert_eq!(fpu.require.len(), 1);
+        assert_eq!(fpu.require[0].attributes["Dcore"], "Cortex-M4");
+        assert_eq!(fpu.require[0].attributes["Dfpu"], "FPU");
+        assert_eq!(fpu.deny[0].attributes["Pname"], "cm7");
+        assert!(fpu.accept.is_empty());
+        assert_eq!(
+            conditions.get("GCC").unwrap().require[0]
+                .compiler
+                .as_deref(),
+            Some("GCC")
+        );
+        assert!(conditions.get("IAR").is_none());
+    }
+
+    #[test]
+    fn wildcards() {
+        assert!(wildcard_match("STM32F4*", "STM32F407VG"));
+        assert!(wildcard_match("STM32F4?7VG", "STM32F407VG"));
+        assert!(wildcard_match("*", ""));
+        assert!(!wildcard_match("STM32F4?", "STM32F407"));
+        assert!(!wildcard_match("STM32F1*", "STM32F407VG"));
+    }
+
+    #[test]
+    fn processor_attributes() {
+        let conditions = Conditions::from_string(
+            r#"<conditions>
+              <condition id="Little">
+                <require Dendian="Little-endian"/>
+              </condition>
+              <condition id="Not big">
+                <deny Dendian="Big-endian"/>
+              </condition>
+              <condition id="M4 FPU">
+                <require Dcore="Cortex-M4" Dfpu="FPU" Pname="CM4"/>
+              </condition>
+              <condition id="Made up core">
+                <require Dcore="Made-Up"/>
+              </condition>
+              <condition id="Secure">
+                <require Dsecure="Secure"/>
+              </condition>
+            </conditions>"#,
+        )
+        .unwrap();
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="Family">
+                <device Dname="Little">
+                  <processor Pname="cm4" Dcore="Cortex-M4" Dfpu="SP_FPU" Dendian="Little-endian"/>
+                </device>
+                <device Dname="Big">
+                  <processor Dcore="Cortex-M4" Dfpu="NO_FPU" Dendian="Big-endian"/>
+                </device>
+                <device Dname="Either">
+                  <processor Dcore="Cortex-R5" Dendian="Configurable"/>
+                </device>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        let ctx = ConditionContext::default();
+        let applies = |id: &str| {
+            let mut names: Vec<&str> = devices
+                .0
+                .values()
+                .filter(|d| d.applies(id, &conditions, &ctx))
+                .map(|d| d.name.as_str())
+                .collect();
+            names.sort();
+            names
+        };
+        assert_eq!(applies("Little"), ["Either", "Little"]);
+        assert_eq!(applies("Not big"), ["Little"]);
+        assert_eq!(applies("M4 FPU"), ["Little"]);
+        assert!(applies("Made up core").is_empty());
+        assert!(applies("Secure").is_empty());
+        assert_eq!(
+            conditions.get("Secure").unwrap().require[0].unsupported,
+            ["Dsecure"]
+        );
+    }
+
+    #[test]
+    fn require_core() {
+        let conditions = Conditions::from_string(
+            r#"<conditions>
+              <condition id="CM7">
+                <require Dcore="Cortex-M7"/>
+              </condition>
+            </conditions>"#,
+        )
+        .unwrap();
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="STM32H7" Dvendor="STMicroelectronics:13">
+                <memory id="IROM1" access="rx" start="0x08000000" size="0x100000"/>
+                <algorithm name="CM4.FLM" start="0x08000000" size="0x100000"/>
+                <algorithm name="CM7.FLM" start="0x08000000" size="0x100000" condition="CM7"/>
+                <device Dname="STM32H745">
+                  <processor Pname="CM7" Dcore="Cortex-M7"/>
+                  <processor Pname="CM4" Dcore="Cortex-M4"/>
+                  <memory id="ITCM" access="rwx" start="0x0" size="0x10000" condition="CM7"/>
+                </device>
+                <device Dname="STM32H7B0">
+                  <processor Dcore="Cortex-M4"/>
+                </device>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        let ctx = ConditionContext::default();
+        let algorithms = |name: &str| {
+            let device = &devices.0[name];
+            device
+                .algorithms
+                .iter()
+                .filter(|a| {
+                    a.condition
+                        .as_deref()
+                        .map_or(true, |id| device.applies(id, &conditions, &ctx))
+                })
+                .map(|a| a.file_name.to_str().unwrap())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(algorithms("STM32H745"), ["CM4.FLM", "CM7.FLM"]);
+        assert_eq!(algorithms("STM32H7B0"), ["CM4.FLM"]);
+
+        let h745 = &devices.0["STM32H745"];
+        assert_eq!(h745.memories.0["ITCM"].condition.as_deref(), Some("CM7"));
+        assert!(h745.applies("CM7", &conditions, &ctx));
+        assert!(!h745.applies("CM33", &conditions, &ctx));
     }
 }
@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+
+use anyhow::{format_err, Error};
+
+use super::{Algorithm, Device, Memories, Memory, Processor};
+
+/// Assembles a [`Device`] from known memory and algorithm data, for targets without a PDSC,
+/// see [`Device::builder`].
+#[derive(Debug, Clone)]
+pub struct CustomDeviceBuilder {
+    name: String,
+    family: String,
+    vendor: Option<String>,
+    description: Option<String>,
+    processors: Vec<Processor>,
+    memories: Vec<Memory>,
+    algorithms: Vec<Algorithm>,
+}
+
+impl Device {
+    /// Starts building a device named `name` in `family`, without a PDSC.
+    pub fn builder(name: impl Into<String>, family: impl Into<String>) -> CustomDeviceBuilder {
+        CustomDeviceBuilder {
+            name: name.into(),
+            family: family.into(),
+            vendor: None,
+            description: None,
+            processors: Vec::new(),
+            memories: Vec::new(),
+            algorithms: Vec::new(),
+        }
+    }
+}
+
+impl CustomDeviceBuilder {
+    pub fn vendor(mut self, vendor: impl Into<String>) -> Self {
+        self.vendor = Some(vendor.into());
+        self
+    }
+
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Adds a processor. The first one added is the boot core.
+    pub fn processor(mut self, processor: Processor) -> Self {
+        self.processors.push(processor);
+        self
+    }
+
+    /// Adds a memory region, identified by its [`Memory::name`].
+    pub fn memory(mut self, memory: Memory) -> Self {
+        self.memories.push(memory);
+        self
+    }
+
+    pub fn algorithm(mut self, algorithm: Algorithm) -> Self {
+        self.algorithms.push(algorithm);
+        self
+    }
+
+    /// Checks the device is usable and builds it.
+    ///
+    /// A device needs a name and at least one processor, and processors must differ in name or
+    /// unit. Memory regions must have distinct names and a non-zero size, and may only overlap
+    /// if they are visible to different processors. Algorithms must have a non-zero size.
+    pub fn build(self) -> Result<Device, Error> {
+        if self.name.trim().is_empty() {
+            return Err(format_err!("Device found without a name"));
+        }
+        if self.processors.is_empty() {
+            return Err(format_err!(
+                "Device found without a processor {}",
+                self.name
+            ));
+        }
+        for (idx, processor) in self.processors.iter().enumerate() {
+            if self.processors[..idx]
+                .iter()
+                .any(|p| p.name == processor.name && p.unit == processor.unit)
+            {
+                return Err(format_err!(
+                    "{}: duplicate processor {} unit {}",
+                    self.name,
+                    processor.name.as_deref().unwrap_or("<unnamed>"),
+                    processor.unit
+                ));
+            }
+        }
+        for (idx, memory) in self.memories.iter().enumerate() {
+            if memory.size == 0 {
+                return Err(format_err!(
+                    "{}: memory {} is empty",
+                    self.name,
+                    memory.name
+                ));
+            }
+            for other in &self.memories[..idx] {
+                if other.name == memory.name {
+                    return Err(format_err!(
+                        "{}: duplicate memory {}",
+                        self.name,
+                        memory.name
+                    ));
+                }
+                let shared = other.p_name.is_none()
+                    || memory.p_name.is_none()
+                    || other.p_name == memory.p_name;
+                if shared && other.start < memory.end() && memory.start < other.end() {
+                    return Err(format_err!(
+                        "{}: memory {} overlaps {}",
+                        self.name,
+                        memory.name,
+                        other.name
+                    ));
+                }
+            }
+        }
+        if let Some(algorithm) = self.algorithms.iter().find(|a| a.size == 0) {
+            return Err(format_err!(
+                "{}: algorithm {} programs no memory",
+                self.name,
+                algorithm.file_name.display()
+            ));
+        }
+
+        let mut processors = self.processors;
+        for processor in processors.iter_mut() {
+            processor.is_boot_core = false;
+        }
+        processors[0].is_boot_core = true;
+        Ok(Device {
+            name: self.name,
+            memories: Memories(
+                self.memories
+                    .into_iter()
+                    .map(|m| (m.name.clone(), m))
+                    .collect(),
+            ),
+            algorithms: self.algorithms,
+            processors,
+            vendor: self.vendor,
+            family: self.family,
+            sub_family: None,
+            base_device: None,
+            condition: None,
+            description: self.description,
+            extra_attributes: HashMap::new(),
+            pack_version: None,
+            variants: Vec::new(),
+            silicon_revision: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::pdsc::{AlgorithmStyle, Core};
+
+    fn algorithm(start: u64, size: u64) -> Algorithm {
+        Algorithm {
+            file_name: "Flash/Custom.FLM".into(),
+            start,
+            size,
+            default: true,
+            ram_start: Some(0x2000_0000),
+            ram_size: Some(0x1000),
+            style: AlgorithmStyle::CMSIS,
+            p_name: None,
+            condition: None,
+        }
+    }
+
+    #[test]
+    fn custom_device() {
+        let mut flash = Memory::new("FLASH", "rx", 0x0800_0000, 0x10_0000);
+        flash.startup = true;
+        let device = Device::builder("Custom", "Boards")
+            .vendor("Me:0")
+            .processor(Processor::new(Core::CortexM4))
+            .memory(flash)
+            .memory(Memory::new("SRAM", "rwx", 0x2000_0000, 0x2_0000))
+            .algorithm(algorithm(0x0800_0000, 0x10_0000))
+            .build()
+            .unwrap();
+        assert_eq!(device.vendor_name(), Some("Me"));
+        assert_eq!(device.total_flash(), 0x10_0000);
+        assert_eq!(device.total_ram(), 0x2_0000);
+        assert!(device.boot_core().is_some());
+        let flash = device.default_rom().unwrap();
+        assert_eq!(
+            device.primary_algorithm(flash).unwrap().file_name,
+            std::path::Path::new("Flash/Custom.FLM")
+        );
+        assert_eq!(device.validate(), vec![]);
+    }
+
+    #[test]
+    fn custom_device_is_validated() {
+        let base = || Device::builder("Custom", "Boards").processor(Processor::new(Core::CortexM0));
+        assert!(base().build().is_ok());
+        assert!(Device::builder("Custom", "Boards").build().is_err());
+        assert!(Device::builder(" ", "Boards")
+            .processor(Processor::new(Core::CortexM0))
+            .build()
+            .is_err());
+        assert!(base()
+            .processor(Processor::new(Core::CortexM0))
+            .build()
+            .is_err());
+        assert!(base()
+            .memory(Memory::new("EMPTY", "rw", 0x2000_0000, 0))
+            .build()
+            .is_err());
+        assert!(base()
+            .memory(Memory::new("FLASH", "rx", 0x0800_0000, 0x1000))
+            .memory(Memory::new("FLASH", "rx", 0x0900_0000, 0x1000))
+            .build()
+            .is_err());
+        assert!(base()
+            .memory(Memory::new("A", "rx", 0x0800_0000, 0x1000))
+            .memory(Memory::new("B", "rx", 0x0800_0800, 0x1000))
+            .build()
+            .is_err());
+        assert!(base()
+            .memory(Memory::new("A", "rx", 0x0800_0000, 0x1000))
+            .memory(Memory::new("B", "rx", 0x0800_1000, 0x1000))
+            .build()
+            .is_ok());
+        assert!(base().algorithm(algorithm(0, 0)).build().is_err());
+    }
+}
@@ -1,13 +1,25 @@
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::cmp::{Ordering, Reverse};
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
 use std::str::FromStr;
 
+use super::validate::resolve;
+use crate::pdsc::DeviceName;
+use crate::utils::parse::check_nesting;
 use crate::utils::prelude::*;
+use crate::utils::{compare_versions, Diagnostic, StableHasher};
 use anyhow::{format_err, Error};
 use roxmltree::Node;
-use serde::{Deserialize, Serialize};
+use serde::ser::SerializeStruct;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A processor core, written as its `Dcore` value, e.g. `Cortex-M0+`, when displayed or
+/// serialized.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Core {
     Any,
     CortexM0,
@@ -43,51 +55,310 @@ pub enum Core {
     CortexA57,
     CortexA72,
     CortexA73,
+    CortexA55,
+    CortexA65,
+    CortexA75,
+    CortexA76,
+    CortexA77,
+    CortexA78,
 }
 
 impl FromStr for Core {
     type Err = Error;
+    /// Parses a `Dcore` value, ignoring case and surrounding whitespace.
     fn from_str(from: &str) -> Result<Self, Error> {
-        match from {
-            "Cortex-M0" => Ok(Core::CortexM0),
-            "Cortex-M0+" => Ok(Core::CortexM0Plus),
-            "Cortex-M1" => Ok(Core::CortexM1),
-            "Cortex-M3" => Ok(Core::CortexM3),
-            "Cortex-M4" => Ok(Core::CortexM4),
-            "Cortex-M7" => Ok(Core::CortexM7),
-            "Cortex-M23" => Ok(Core::CortexM23),
-            "Cortex-M33" => Ok(Core::CortexM33),
-            "Cortex-M35P" => Ok(Core::CortexM35P),
-            "Cortex-M55" => Ok(Core::CortexM55),
-            "Cortex-M85" => Ok(Core::CortexM85),
-            "Star-MC1" => Ok(Core::StarMC1),
+        match from.trim().to_ascii_uppercase().as_str() {
+            "CORTEX-M0" => Ok(Core::CortexM0),
+            "CORTEX-M0+" => Ok(Core::CortexM0Plus),
+            "CORTEX-M1" => Ok(Core::CortexM1),
+            "CORTEX-M3" => Ok(Core::CortexM3),
+            "CORTEX-M4" => Ok(Core::CortexM4),
+            "CORTEX-M7" => Ok(Core::CortexM7),
+            "CORTEX-M23" => Ok(Core::CortexM23),
+            "CORTEX-M33" => Ok(Core::CortexM33),
+            "CORTEX-M35P" => Ok(Core::CortexM35P),
+            "CORTEX-M55" => Ok(Core::CortexM55),
+            "CORTEX-M85" => Ok(Core::CortexM85),
+            "STAR-MC1" => Ok(Core::StarMC1),
             "SC000" => Ok(Core::SC000),
             "SC300" => Ok(Core::SC300),
             "ARMV8MBL" => Ok(Core::ARMV8MBL),
             "ARMV8MML" => Ok(Core::ARMV8MML),
-            "Cortex-R4" => Ok(Core::CortexR4),
-            "Cortex-R5" => Ok(Core::CortexR5),
-            "Cortex-R7" => Ok(Core::CortexR7),
-            "Cortex-R8" => Ok(Core::CortexR8),
-            "Cortex-A5" => Ok(Core::CortexA5),
-            "Cortex-A7" => Ok(Core::CortexA7),
-            "Cortex-A8" => Ok(Core::CortexA8),
-            "Cortex-A9" => Ok(Core::CortexA9),
-            "Cortex-A15" => Ok(Core::CortexA15),
-            "Cortex-A17" => Ok(Core::CortexA17),
-            "Cortex-A32" => Ok(Core::CortexA32),
-            "Cortex-A35" => Ok(Core::CortexA35),
-            "Cortex-A53" => Ok(Core::CortexA53),
-            "Cortex-A57" => Ok(Core::CortexA57),
-            "Cortex-A72" => Ok(Core::CortexA72),
-            "Cortex-A73" => Ok(Core::CortexA73),
+            "ARMV81MML" => Ok(Core::ARMV81MML),
+            "CORTEX-R4" => Ok(Core::CortexR4),
+            "CORTEX-R5" => Ok(Core::CortexR5),
+            "CORTEX-R7" => Ok(Core::CortexR7),
+            "CORTEX-R8" => Ok(Core::CortexR8),
+            "CORTEX-A5" => Ok(Core::CortexA5),
+            "CORTEX-A7" => Ok(Core::CortexA7),
+            "CORTEX-A8" => Ok(Core::CortexA8),
+            "CORTEX-A9" => Ok(Core::CortexA9),
+            "CORTEX-A15" => Ok(Core::CortexA15),
+            "CORTEX-A17" => Ok(Core::CortexA17),
+            "CORTEX-A32" => Ok(Core::CortexA32),
+            "CORTEX-A35" => Ok(Core::CortexA35),
+            "CORTEX-A53" => Ok(Core::CortexA53),
+            "CORTEX-A57" => Ok(Core::CortexA57),
+            "CORTEX-A72" => Ok(Core::CortexA72),
+            "CORTEX-A73" => Ok(Core::CortexA73),
+            "CORTEX-A55" => Ok(Core::CortexA55),
+            "CORTEX-A65" => Ok(Core::CortexA65),
+            "CORTEX-A75" => Ok(Core::CortexA75),
+            "CORTEX-A76" => Ok(Core::CortexA76),
+            "CORTEX-A77" => Ok(Core::CortexA77),
+            "CORTEX-A78" => Ok(Core::CortexA78),
             "*" => Ok(Core::Any),
-            unknown => Err(format_err!("Unknown core {}", unknown)),
+            _ => Err(format_err!("Unknown core {}", from)),
+        }
+    }
+}
+
+impl fmt::Display for Core {
+    /// Writes the `Dcore` value [`Core::from_str`] parses this core from.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Core::Any => "*",
+            Core::CortexM0 => "Cortex-M0",
+            Core::CortexM0Plus => "Cortex-M0+",
+            Core::CortexM1 => "Cortex-M1",
+            Core::CortexM3 => "Cortex-M3",
+            Core::CortexM4 => "Cortex-M4",
+            Core::CortexM7 => "Cortex-M7",
+            Core::CortexM23 => "Cortex-M23",
+            Core::CortexM33 => "Cortex-M33",
+            Core::CortexM35P => "Cortex-M35P",
+            Core::CortexM55 => "Cortex-M55",
+            Core::CortexM85 => "Cortex-M85",
+            Core::StarMC1 => "Star-MC1",
+            Core::SC000 => "SC000",
+            Core::SC300 => "SC300",
+            Core::ARMV8MBL => "ARMV8MBL",
+            Core::ARMV8MML => "ARMV8MML",
+            Core::ARMV81MML => "ARMV81MML",
+            Core::CortexR4 => "Cortex-R4",
+            Core::CortexR5 => "Cortex-R5",
+            Core::CortexR7 => "Cortex-R7",
+            Core::CortexR8 => "Cortex-R8",
+            Core::CortexA5 => "Cortex-A5",
+            Core::CortexA7 => "Cortex-A7",
+            Core::CortexA8 => "Cortex-A8",
+            Core::CortexA9 => "Cortex-A9",
+            Core::CortexA15 => "Cortex-A15",
+            Core::CortexA17 => "Cortex-A17",
+            Core::CortexA32 => "Cortex-A32",
+            Core::CortexA35 => "Cortex-A35",
+            Core::CortexA53 => "Cortex-A53",
+            Core::CortexA57 => "Cortex-A57",
+            Core::CortexA72 => "Cortex-A72",
+            Core::CortexA73 => "Cortex-A73",
+            Core::CortexA55 => "Cortex-A55",
+            Core::CortexA65 => "Cortex-A65",
+            Core::CortexA75 => "Cortex-A75",
+            Core::CortexA76 => "Cortex-A76",
+            Core::CortexA77 => "Cortex-A77",
+            Core::CortexA78 => "Cortex-A78",
+        })
+    }
+}
+
+impl Core {
+    /// Every core, in declaration order.
+    const ALL: [Core; 40] = [
+        Core::Any,
+        Core::CortexM0,
+        Core::CortexM0Plus,
+        Core::CortexM1,
+        Core::CortexM3,
+        Core::CortexM4,
+        Core::CortexM7,
+        Core::CortexM23,
+        Core::CortexM33,
+        Core::CortexM35P,
+        Core::CortexM55,
+        Core::CortexM85,
+        Core::StarMC1,
+        Core::SC000,
+        Core::SC300,
+        Core::ARMV8MBL,
+        Core::ARMV8MML,
+        Core::ARMV81MML,
+        Core::CortexR4,
+        Core::CortexR5,
+        Core::CortexR7,
+        Core::CortexR8,
+        Core::CortexA5,
+        Core::CortexA7,
+        Core::CortexA8,
+        Core::CortexA9,
+        Core::CortexA15,
+        Core::CortexA17,
+        Core::CortexA32,
+        Core::CortexA35,
+        Core::CortexA53,
+        Core::CortexA57,
+        Core::CortexA72,
+        Core::CortexA73,
+        Core::CortexA55,
+        Core::CortexA65,
+        Core::CortexA75,
+        Core::CortexA76,
+        Core::CortexA77,
+        Core::CortexA78,
+    ];
+}
+
+impl Serialize for Core {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Core {
+    /// Reads a `Dcore` value, or the variant name cores were serialized as before, e.g.
+    /// `CortexM0Plus`, so that indexes written by older versions still load.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse().or_else(|_| {
+            Core::ALL
+                .into_iter()
+                .find(|core| format!("{:?}", core) == name)
+                .ok_or_else(|| de::Error::custom(format!("Unknown core {}", name)))
+        })
+    }
+}
+
+/// The Arm architecture profile implemented by a [`Core`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Architecture {
+    /// The architecture of the `*` wildcard core, which matches any architecture.
+    Any,
+    Armv6M,
+    Armv7M,
+    Armv7EM,
+    Armv8MBaseline,
+    Armv8MMainline,
+    Armv81MMainline,
+    Armv7R,
+    Armv7A,
+    Armv8A,
+    Armv82A,
+}
+
+impl Core {
+    pub fn architecture(&self) -> Architecture {
+        match self {
+            Core::Any => Architecture::Any,
+            Core::CortexM0 | Core::CortexM0Plus | Core::CortexM1 | Core::SC000 => {
+                Architecture::Armv6M
+            }
+            Core::CortexM3 | Core::SC300 => Architecture::Armv7M,
+            Core::CortexM4 | Core::CortexM7 => Architecture::Armv7EM,
+            Core::CortexM23 | Core::ARMV8MBL => Architecture::Armv8MBaseline,
+            Core::CortexM33 | Core::CortexM35P | Core::StarMC1 | Core::ARMV8MML => {
+                Architecture::Armv8MMainline
+            }
+            Core::CortexM55 | Core::CortexM85 | Core::ARMV81MML => Architecture::Armv81MMainline,
+            Core::CortexR4 | Core::CortexR5 | Core::CortexR7 | Core::CortexR8 => {
+                Architecture::Armv7R
+            }
+            Core::CortexA5
+            | Core::CortexA7
+            | Core::CortexA8
+            | Core::CortexA9
+            | Core::CortexA15
+            | Core::CortexA17 => Architecture::Armv7A,
+            Core::CortexA32
+            | Core::CortexA35
+            | Core::CortexA53
+            | Core::CortexA57
+            | Core::CortexA72
+            | Core::CortexA73 => Architecture::Armv8A,
+            Core::CortexA55
+            | Core::CortexA65
+            | Core::CortexA75
+            | Core::CortexA76
+            | Core::CortexA77
+            | Core::CortexA78 => Architecture::Armv82A,
+        }
+    }
+
+    /// Returns true for M-profile cores, including `SC000`, `SC300`, `Star-MC1` and the
+    /// generic `ARMV8M*` cores.
+    pub fn is_cortex_m(&self) -> bool {
+        matches!(
+            self.architecture(),
+            Architecture::Armv6M
+                | Architecture::Armv7M
+                | Architecture::Armv7EM
+                | Architecture::Armv8MBaseline
+                | Architecture::Armv8MMainline
+                | Architecture::Armv81MMainline
+        )
+    }
+
+    /// Returns true for R-profile cores.
+    pub fn is_cortex_r(&self) -> bool {
+        matches!(self.architecture(), Architecture::Armv7R)
+    }
+
+    /// Returns true for A-profile cores.
+    pub fn is_cortex_a(&self) -> bool {
+        matches!(
+            self.architecture(),
+            Architecture::Armv7A | Architecture::Armv8A | Architecture::Armv82A
+        )
+    }
+
+    /// Returns true if the cores are the same, or either is the `*` wildcard [`Core::Any`].
+    pub fn matches(&self, other: &Core) -> bool {
+        self == other || *self == Core::Any || *other == Core::Any
+    }
+}
+
+/// Strips a trailing revision marker, such as `+` or ` r0p1`, from a `Dcore` value.
+fn strip_core_revision(from: &str) -> &str {
+    let from = from.trim_end_matches('+');
+    if let Some(idx) = from.rfind('r') {
+        let revision = &from[idx + 1..];
+        let is_revision = revision.split_once('p').is_some_and(|(r, p)| {
+            !r.is_empty()
+                && !p.is_empty()
+                && r.chars().all(|c| c.is_ascii_digit())
+                && p.chars().all(|c| c.is_ascii_digit())
+        });
+        if is_revision {
+            return from[..idx].trim_end_matches([' ', '-', '_']);
         }
     }
+    from
+}
+
+/// Parses a `Dcore` attribute, tolerating some malformed values found in real packs.
+///
+/// Some packs write `Cortex-M4F` to imply a single precision FPU, or append a revision marker as
+/// in `Cortex-M33+` or `Cortex-M4 r0p1`. These are accepted as the base core with a warning, and
+/// the FPU implied by an `F` suffix is returned alongside it.
+fn parse_core(from: &str) -> Result<(Core, Option<FPU>), Error> {
+    let from = from.trim();
+    if let Ok(core) = from.parse() {
+        return Ok((core, None));
+    }
+    let stripped = strip_core_revision(from);
+    if let Ok(core) = stripped.parse() {
+        log::warn!("Ignoring revision in core {}", from);
+        return Ok((core, None));
+    }
+    if let Some(core) = stripped
+        .strip_suffix(['F', 'f'])
+        .and_then(|c| c.parse().ok())
+    {
+        log::warn!("Core {} implies an FPU; use Dfpu instead", from);
+        return Ok((core, Some(FPU::SinglePrecision)));
+    }
+    Err(format_err!("Unknown core {}", from))
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum FPU {
     None,
     SinglePrecision,
@@ -110,7 +381,28 @@ impl FromStr for FPU {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl FPU {
+    /// Returns true if there is a floating point unit.
+    pub fn is_present(&self) -> bool {
+        *self != FPU::None
+    }
+
+    /// Returns true if the floating point unit handles double precision.
+    pub fn is_double_precision(&self) -> bool {
+        *self == FPU::DoublePrecision
+    }
+
+    /// The widest floating point type the unit handles, in bits, or `None` without one.
+    pub fn precision_bits(&self) -> Option<u32> {
+        match self {
+            FPU::None => None,
+            FPU::SinglePrecision => Some(32),
+            FPU::DoublePrecision => Some(64),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum MPU {
     NotPresent,
     Present,
@@ -129,65 +421,232 @@ impl FromStr for MPU {
     }
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+/// Whether a core implements the Armv8-M Security Extension, from `Dtz`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TrustZone {
+    #[default]
+    NotPresent,
+    Present,
+}
+
+impl FromStr for TrustZone {
+    type Err = Error;
+    fn from_str(from: &str) -> Result<Self, Error> {
+        match from {
+            "TZ" => Ok(TrustZone::Present),
+            "NO_TZ" => Ok(TrustZone::NotPresent),
+            unknown => Err(format_err!("Unknown trustzone {}", unknown)),
+        }
+    }
+}
+
+/// The byte order of a core, from `Dendian`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Endian {
+    #[default]
+    Little,
+    Big,
+    /// The byte order is chosen when the core is configured, e.g. at reset on a Cortex-R.
+    Configurable,
+}
+
+impl FromStr for Endian {
+    type Err = Error;
+    fn from_str(from: &str) -> Result<Self, Error> {
+        match from {
+            "Little-endian" => Ok(Endian::Little),
+            "Big-endian" => Ok(Endian::Big),
+            "Configurable" => Ok(Endian::Configurable),
+            unknown => Err(format_err!("Unknown endian {}", unknown)),
+        }
+    }
+}
+
+impl fmt::Display for Endian {
+    /// Formats the byte order as written in `Dendian`, e.g. `Little-endian`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Endian::Little => "Little-endian",
+            Endian::Big => "Big-endian",
+            Endian::Configurable => "Configurable",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct Processor {
     pub core: Core,
+    /// The core revision from `Dcorever`, e.g. `r0p1`, kept verbatim.
+    pub core_revision: Option<String>,
     pub fpu: FPU,
     pub mpu: MPU,
+    /// The number of MPU regions, from `DnumMpuRegions`, when the pack declares it.
+    pub mpu_regions: Option<u32>,
+    #[serde(default)]
+    pub tz: TrustZone,
+    #[serde(default)]
+    pub endian: Endian,
     pub ap: AccessPort,
     pub dp: u8,
-    pub address: Option<u32>,
+    pub address: Option<u64>,
     pub svd: Option<String>,
     pub name: Option<String>,
     pub unit: usize,
     pub default_reset_sequence: Option<String>,
+    /// The SWD multidrop `TARGETSEL` value of the debug port reaching this processor.
+    #[serde(default)]
+    pub targetsel: Option<u32>,
+    /// Attributes of `<processor>` this crate doesn't model, when captured with
+    /// [`ParseOptions::capture_extra`].
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub extra_attributes: HashMap<String, String>,
+    /// This is the core that boots the device, and so the one to reset first.
+    ///
+    /// The pack format has no way to declare the boot core, so unit 0 of the first processor
+    /// declared, by the outermost element that declares any, is assumed to be it. Exactly one
+    /// processor of a device is the boot core.
+    #[serde(default)]
+    pub is_boot_core: bool,
 }
 
+/// The most units a single `<processor>` may declare. `Punits` expands into one [`Processor`]
+/// per unit, so larger values are clamped rather than trusted.
+const MAX_PROCESSOR_UNITS: usize = 64;
+
 #[derive(Debug, Clone)]
 struct ProcessorBuilder {
     core: Option<Core>,
+    core_revision: Option<String>,
     units: Option<usize>,
+    /// The unit a `Punit` scoped element describes; such an element yields that unit alone.
+    unit: Option<usize>,
     name: Option<String>,
     fpu: Option<FPU>,
     mpu: Option<MPU>,
+    mpu_regions: Option<u32>,
+    tz: Option<TrustZone>,
+    endian: Option<Endian>,
+    extra_attributes: HashMap<String, String>,
+}
+
+impl Processor {
+    /// Unit 0 of an unnamed `core` without FPU or MPU, reached through access port 0 of debug
+    /// port 0.
+    pub fn new(core: Core) -> Self {
+        Processor {
+            core,
+            core_revision: None,
+            fpu: FPU::None,
+            mpu: MPU::NotPresent,
+            mpu_regions: None,
+            tz: TrustZone::NotPresent,
+            endian: Endian::Little,
+            ap: AccessPort::default(),
+            dp: 0,
+            address: None,
+            svd: None,
+            name: None,
+            unit: 0,
+            default_reset_sequence: None,
+            targetsel: None,
+            extra_attributes: HashMap::new(),
+            is_boot_core: false,
+        }
+    }
+
+    /// Returns true if the `<debug>` element `d` applies to this processor.
+    pub fn matches_debug(&self, d: &Debug) -> bool {
+        d.applies_to(self.name.as_deref(), self.unit)
+    }
+
+    /// The GCC `-mfpu` value for this processor's core and FPU, or `None` if there is no FPU or
+    /// GCC should be left to pick one from `-mcpu`.
+    ///
+    /// A double precision FPU declared on a core that only comes with a single precision one,
+    /// such as a Cortex-M4 or M33, is treated as single precision.
+    pub fn fpu_gcc_flag(&self) -> Option<&'static str> {
+        if !self.fpu.is_present() {
+            return None;
+        }
+        let double = self.fpu.is_double_precision();
+        match self.core {
+            Core::CortexM4 => Some("fpv4-sp-d16"),
+            Core::CortexM7 | Core::CortexM55 | Core::CortexM85 | Core::ARMV81MML if double => {
+                Some("fpv5-d16")
+            }
+            Core::CortexM7
+            | Core::CortexM33
+            | Core::CortexM35P
+            | Core::CortexM55
+            | Core::CortexM85
+            | Core::StarMC1
+            | Core::ARMV8MML
+            | Core::ARMV81MML => Some("fpv5-sp-d16"),
+            Core::CortexR4 | Core::CortexR5 | Core::CortexR7 | Core::CortexR8 if double => {
+                Some("vfpv3-d16")
+            }
+            Core::CortexR4 | Core::CortexR5 | Core::CortexR7 | Core::CortexR8 => Some("vfpv3xd"),
+            _ => None,
+        }
+    }
 }
 
 impl ProcessorBuilder {
     fn merge(&mut self, other: &Self) {
-        self.core = self.core.clone().or(other.core.clone());
+        if self.core.is_none() {
+            self.core.clone_from(&other.core);
+        }
+        if self.core_revision.is_none() {
+            self.core_revision.clone_from(&other.core_revision);
+        }
         self.units = self.units.or(other.units);
-        self.name = self.name.clone().or(other.name.clone());
-        self.fpu = self.fpu.clone().or(other.fpu.clone());
-        self.mpu = self.mpu.clone().or(other.mpu.clone());
+        if self.name.is_none() {
+            self.name.clone_from(&other.name);
+        }
+        if self.fpu.is_none() {
+            self.fpu.clone_from(&other.fpu);
+        }
+        if self.mpu.is_none() {
+            self.mpu.clone_from(&other.mpu);
+        }
+        self.mpu_regions = self.mpu_regions.or(other.mpu_regions);
+        if self.tz.is_none() {
+            self.tz.clone_from(&other.tz);
+        }
+        self.endian = self.endian.or(other.endian);
+        inherit_extra_attributes(&mut self.extra_attributes, &other.extra_attributes);
     }
     fn build(self, debugs: &[Debug]) -> Result<Vec<Processor>, Error> {
-        let units = self.units.unwrap_or(1);
+        let units = match self.unit {
+            Some(unit) => unit..unit + 1,
+            None => 0..self.units.unwrap_or(1),
+        };
         let name = self.name.clone();
 
-        (0..units)
+        units
             .map(|unit| {
                 // The attributes we're interested in may be spread across multiple debug
                 // attributes defined in the family, subfamily, or device; and which may or may not
                 // be specific to a given Pname or Punit.
                 //
                 // We'll prioritize the first element with the attribute we're interested in, since
-                // family and subfamily debug elements are appended after device debug elements.
-                let debugs_iterator = debugs.iter().filter(|debug| {
-                    // If Pname or Punit are present on the <debug> element, they must match.
-                    debug
-                        .name
-                        .as_ref()
-                        .map_or(true, |n| Some(n) == name.as_ref())
-                        && debug.unit.map_or(true, |u| u == unit)
-                });
+                // family and subfamily debug elements are appended after device debug elements,
+                // and within each element, unit and Pname scoped elements come first.
+                let debugs_iterator = debugs
+                    .iter()
+                    .filter(|debug| debug.applies_to(name.as_deref(), unit));
 
                 Ok(Processor {
                     core: self
                         .core
                         .clone()
                         .ok_or_else(|| format_err!("No Core found!"))?,
+                    core_revision: self.core_revision.clone(),
                     fpu: self.fpu.clone().unwrap_or(FPU::None),
                     mpu: self.mpu.clone().unwrap_or(MPU::NotPresent),
+                    mpu_regions: self.mpu_regions,
+                    tz: self.tz.clone().unwrap_or_default(),
+                    endian: self.endian.unwrap_or_default(),
                     dp: debugs_iterator
                         .clone()
                         .find_map(|d| d.dp)
@@ -197,12 +656,21 @@ impl ProcessorBuilder {
                         .find_map(|d| d.ap)
                         .unwrap_or_default(),
                     address: debugs_iterator.clone().find_map(|d| d.address),
-                    svd: debugs_iterator.clone().find_map(|d| d.svd.clone()),
+                    // A core specific SVD wins over one shared by every core, even when the
+                    // shared one is declared by a more specific element.
+                    svd: debugs_iterator
+                        .clone()
+                        .filter(|d| d.name.is_some() || d.unit.is_some())
+                        .chain(debugs_iterator.clone())
+                        .find_map(|d| d.svd.clone()),
                     name: name.clone(),
                     unit,
                     default_reset_sequence: debugs_iterator
                         .clone()
                         .find_map(|d| d.default_reset_sequence.clone()),
+                    targetsel: debugs_iterator.clone().find_map(|d| d.targetsel),
+                    extra_attributes: self.extra_attributes.clone(),
+                    is_boot_core: false,
                 })
             })
             .collect::<Result<Vec<_>, _>>()
@@ -211,40 +679,78 @@ impl ProcessorBuilder {
 
 impl FromElem for ProcessorBuilder {
     fn from_elem(e: &Node) -> Result<Self, Error> {
+        Self::from_elem_with(e, &ParseOptions::default())
+    }
+
+    fn from_elem_with(e: &Node, opts: &ParseOptions) -> Result<Self, Error> {
+        let (core, implied_fpu) = match e.attribute("Dcore").map(parse_core) {
+            Some(Ok((core, fpu))) => (Some(core), fpu),
+            _ => (None, None),
+        };
+        let units = attr_parse(e, "Punits").ok().map(|units: usize| {
+            if units > MAX_PROCESSOR_UNITS {
+                log::warn!(
+                    "Punits {} exceeds the maximum of {}, clamping",
+                    units,
+                    MAX_PROCESSOR_UNITS
+                );
+            }
+            units.min(MAX_PROCESSOR_UNITS)
+        });
         Ok(ProcessorBuilder {
-            core: attr_parse(e, "Dcore").ok(),
-            units: attr_parse(e, "Punits").ok(),
-            fpu: attr_parse(e, "Dfpu").ok(),
+            core,
+            core_revision: attr_parse(e, "Dcorever").ok(),
+            units,
+            unit: attr_parse(e, "Punit").ok(),
+            fpu: attr_parse(e, "Dfpu").ok().or(implied_fpu),
             mpu: attr_parse(e, "Dmpu").ok(),
+            mpu_regions: attr_parse(e, "DnumMpuRegions").ok(),
+            tz: attr_parse(e, "Dtz").ok(),
+            endian: attr_parse(e, "Dendian").ok(),
             name: attr_parse(e, "Pname").ok(),
+            extra_attributes: extra_attributes(
+                e,
+                &[
+                    "Dcore",
+                    "Dcorever",
+                    "Punits",
+                    "Punit",
+                    "Dfpu",
+                    "Dmpu",
+                    "DnumMpuRegions",
+                    "Dtz",
+                    "Dendian",
+                    "Pname",
+                ],
+                opts,
+            ),
         })
     }
 }
 
+/// The processors of a device, and the `Pname` of the first one declared by the outermost
+/// element that declares any, which is taken as the boot core.
 #[derive(Debug, Clone)]
-struct ProcessorsBuilder(Vec<ProcessorBuilder>);
+struct ProcessorsBuilder(Vec<ProcessorBuilder>, Option<String>);
 
 impl ProcessorsBuilder {
-    fn merge(self, parent: &Option<Self>) -> Result<Self, Error> {
+    fn merge(mut self, parent: &Option<Self>) -> Result<Self, Error> {
+        // Devices rarely have more than a handful of processors, so a linear search is cheaper
+        // than building a map, and merging in place keeps them in declaration order.
         if let Some(parent) = parent {
-            let mut current = self
-                .0
-                .into_iter()
-                .map(|p| (p.name.clone(), p))
-                .collect::<HashMap<Option<String>, ProcessorBuilder>>();
-
+            self.1.clone_from(&parent.1);
             for parent in parent.0.iter() {
-                let current = current
-                    .entry(parent.name.clone())
-                    .or_insert_with(|| parent.clone());
-                current.merge(parent);
+                match self
+                    .0
+                    .iter_mut()
+                    .find(|p| p.name == parent.name && p.unit == parent.unit)
+                {
+                    Some(current) => current.merge(parent),
+                    None => self.0.push(parent.clone()),
+                }
             }
-
-            let result = current.into_values().collect();
-            Ok(Self(result))
-        } else {
-            Ok(self)
         }
+        Ok(self)
     }
 
     fn merge_into(&mut self, other: Self) {
@@ -252,9 +758,40 @@ impl ProcessorsBuilder {
     }
 
     fn build(self, debugs: Vec<Debug>) -> Result<Vec<Processor>, Error> {
-        let mut vec = vec![];
-        for processor in self.0.into_iter() {
-            vec.extend(processor.build(&debugs)?);
+        // A `Punit` scoped element inherits from the unscoped element of the same `Pname`, and
+        // replaces the unit that element would otherwise yield.
+        let unscoped: Vec<ProcessorBuilder> = self
+            .0
+            .iter()
+            .filter(|p| p.unit.is_none())
+            .cloned()
+            .collect();
+        let mut vec: Vec<Processor> = vec![];
+        for mut processor in self.0.into_iter() {
+            let scoped = processor.unit.is_some();
+            if scoped {
+                if let Some(general) = unscoped.iter().find(|p| p.name == processor.name) {
+                    processor.merge(general);
+                }
+            }
+            for built in processor.build(&debugs)? {
+                match vec
+                    .iter_mut()
+                    .find(|p| p.name == built.name && p.unit == built.unit)
+                {
+                    Some(existing) if scoped => *existing = built,
+                    Some(_) => {}
+                    None => vec.push(built),
+                }
+            }
+        }
+        match vec.iter_mut().find(|p| p.name == self.1 && p.unit == 0) {
+            Some(boot) => boot.is_boot_core = true,
+            None => {
+                if let Some(first) = vec.first_mut() {
+                    first.is_boot_core = true;
+                }
+            }
         }
         Ok(vec)
     }
@@ -262,40 +799,107 @@ impl ProcessorsBuilder {
 
 impl FromElem for ProcessorsBuilder {
     fn from_elem(e: &Node) -> Result<Self, Error> {
-        Ok(ProcessorsBuilder(vec![ProcessorBuilder::from_elem(e)?]))
+        Self::from_elem_with(e, &ParseOptions::default())
+    }
+
+    fn from_elem_with(e: &Node, opts: &ParseOptions) -> Result<Self, Error> {
+        let processor = ProcessorBuilder::from_elem_with(e, opts)?;
+        let name = processor.name.clone();
+        Ok(ProcessorsBuilder(vec![processor], name))
+    }
+}
+
+/// Adds the extra attributes of a parent element that `extra` doesn't already override.
+fn inherit_extra_attributes(extra: &mut HashMap<String, String>, parent: &HashMap<String, String>) {
+    for (name, value) in parent {
+        extra.entry(name.clone()).or_insert_with(|| value.clone());
     }
 }
 
-#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
 pub enum AccessPort {
     Index(u8),
     Address(u64),
 }
+impl AccessPort {
+    /// The access port index, or for APv2 its address.
+    pub fn as_u64(&self) -> u64 {
+        match *self {
+            AccessPort::Index(index) => index.into(),
+            AccessPort::Address(address) => address,
+        }
+    }
+
+    /// Returns true if this is an APv2 access port identified by address rather than index.
+    pub fn is_address(&self) -> bool {
+        matches!(self, AccessPort::Address(_))
+    }
+}
+
 impl Default for AccessPort {
     fn default() -> Self {
         Self::Index(0)
     }
 }
+/// The processors of a device arranged by the debug port and access port they are reached
+/// through, see [`Device::debug_topology`].
+#[derive(Debug, Clone, Serialize)]
+pub struct DebugTopology<'a> {
+    /// The debug ports, ordered by `__dp`.
+    pub ports: Vec<DebugPortNode<'a>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DebugPortNode<'a> {
+    pub dp: u8,
+    /// The access ports of this debug port, indices first and then APv2 addresses.
+    pub access_ports: Vec<AccessPortNode<'a>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AccessPortNode<'a> {
+    pub ap: AccessPort,
+    /// The processors behind this access port, in declaration order.
+    pub processors: Vec<&'a Processor>,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Debug {
     pub dp: Option<u8>,
     pub ap: Option<AccessPort>,
-    pub address: Option<u32>,
+    pub address: Option<u64>,
     pub svd: Option<String>,
     pub name: Option<String>,
     pub unit: Option<usize>,
     pub default_reset_sequence: Option<String>,
+    /// The SWD multidrop `TARGETSEL` value selecting the debug port, from `__targetsel`.
+    pub targetsel: Option<u32>,
+}
+
+impl Debug {
+    /// Returns true if this `<debug>` element applies to the processor with the given `Pname`
+    /// and unit.
+    ///
+    /// If `Pname` or `Punit` are present on the `<debug>` element, they must match; a `<debug>`
+    /// element without them applies to every processor. `Pname` is compared ignoring ASCII case,
+    /// as packs don't always spell it the same in `<processor>` and `<debug>`.
+    pub fn applies_to(&self, name: Option<&str>, unit: usize) -> bool {
+        self.name.as_deref().map_or(true, |n| {
+            name.is_some_and(|name| n.eq_ignore_ascii_case(name))
+        }) && self.unit.map_or(true, |u| u == unit)
+    }
 }
 
 #[derive(Debug, Clone)]
 struct DebugBuilder {
     dp: Option<u8>,
     ap: Option<AccessPort>,
-    address: Option<u32>,
+    address: Option<u64>,
     svd: Option<String>,
     name: Option<String>,
     unit: Option<usize>,
     default_reset_sequence: Option<String>,
+    targetsel: Option<u32>,
 }
 
 impl DebugBuilder {
@@ -308,17 +912,23 @@ impl DebugBuilder {
             name: self.name,
             unit: self.unit,
             default_reset_sequence: self.default_reset_sequence,
+            targetsel: self.targetsel,
         }
     }
 }
 
 impl DebugBuilder {
-    fn from_elem_and_parent(e: &Node, p: &Node) -> Result<Self, Error> {
+    fn from_elem_and_parent(
+        e: &Node,
+        p: &Node,
+        opts: &ParseOptions,
+        diags: &mut dyn Diagnostics,
+    ) -> Result<Self, Error> {
         let c = p
             .children()
             .map(|n| n.tag_name().name())
             .collect::<Vec<_>>();
-        let (dp, ap) = if c.contains(&"accessportV1") || c.contains(&"accessportV2") {
+        let (dp, ap, ap_targetsel) = if c.contains(&"accessportV1") || c.contains(&"accessportV2") {
             let __apid: u32 = attr_parse(e, "__apid")?;
             let ap = p
                 .children()
@@ -333,39 +943,60 @@ impl DebugBuilder {
                 "accessportV1" => (
                     attr_parse(&ap, "__dp").ok(),
                     attr_parse(&ap, "index").ok().map(AccessPort::Index),
+                    None,
                 ),
                 "accessportV2" => (
                     attr_parse(&ap, "__dp").ok(),
                     attr_parse_hex(&ap, "address").ok().map(AccessPort::Address),
+                    parse_targetsel(&ap, diags),
                 ),
-                _ => unreachable!(),
+                unknown => return Err(format_err!("Unsupported access port {}", unknown)),
             }
         } else {
             (
                 attr_parse(e, "__dp").ok(),
                 attr_parse(e, "__ap").ok().map(AccessPort::Index),
+                None,
             )
         };
 
         Ok(DebugBuilder {
             dp,
             ap,
-            address: attr_parse(e, "address").ok(),
-            svd: attr_parse(e, "svd").ok(),
+            address: e
+                .attribute("address")
+                .and_then(|_| attr_parse_hex(e, "address").ok_report(e, diags)),
+            svd: e.attribute("svd").map(|svd| opts.path_style.normalize(svd)),
             name: attr_parse(e, "Pname").ok(),
             unit: attr_parse(e, "Punit").ok(),
             default_reset_sequence: attr_parse(e, "defaultResetSequence").ok(),
+            targetsel: ap_targetsel.or_else(|| parse_targetsel(e, diags)),
         })
     }
 }
 
+/// The `__targetsel` of `e`, if it has one that fits the 32 bit `TARGETSEL` register.
+///
+/// The attribute belongs on `<accessportV2>`, but some packs put it on `<debug>` instead.
+fn parse_targetsel(e: &Node, diags: &mut dyn Diagnostics) -> Option<u32> {
+    e.attribute("__targetsel")?;
+    attr_parse_hex(e, "__targetsel")
+        .ok_report(e, diags)
+        .and_then(|targetsel| u32::try_from(targetsel).ok_report(e, diags))
+}
+
 #[derive(Debug)]
 struct DebugsBuilder(Vec<DebugBuilder>);
 
 impl DebugsBuilder {
-    fn from_elem_and_parent(e: &Node, p: &Node) -> Result<Self, Error> {
+    fn from_elem_and_parent(
+        e: &Node,
+        p: &Node,
+        opts: &ParseOptions,
+        diags: &mut dyn Diagnostics,
+    ) -> Result<Self, Error> {
         Ok(DebugsBuilder(vec![DebugBuilder::from_elem_and_parent(
-            e, p,
+            e, p, opts, diags,
         )?]))
     }
 }
@@ -376,8 +1007,19 @@ impl DebugsBuilder {
         self
     }
 
+    /// Adds `<debug>` elements declared alongside those already here. A more specific element,
+    /// scoped by `Pname` or `Punit`, is placed before less specific ones so that it wins over
+    /// them regardless of declaration order.
     fn merge_into(&mut self, other: Self) {
-        self.0.extend(other.0)
+        let specificity = |d: &DebugBuilder| d.name.is_some() as u8 + d.unit.is_some() as u8;
+        for debug in other.0 {
+            let idx = self
+                .0
+                .iter()
+                .position(|d| specificity(d) < specificity(&debug))
+                .unwrap_or(self.0.len());
+            self.0.insert(idx, debug);
+        }
     }
 
     fn build(self) -> Vec<Debug> {
@@ -385,7 +1027,7 @@ impl DebugsBuilder {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct MemoryPermissions {
     pub read: bool,
     pub write: bool,
@@ -408,7 +1050,7 @@ impl MemoryPermissions {
             non_secure_callable: false,
         };
         for c in input.chars() {
-            match c {
+            match c.to_ascii_lowercase() {
                 'r' => ret.read = true,
                 'w' => ret.write = true,
                 'x' => ret.execute = true,
@@ -423,6 +1065,85 @@ impl MemoryPermissions {
     }
 }
 
+/// The security state(s) from which a memory region is accessible.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SecurityState {
+    Secure,
+    NonSecure,
+    /// The region is accessible from both security states, e.g. `access="rwsn"`.
+    Both,
+    /// The `access` attribute does not mention a security state.
+    Unspecified,
+}
+
+impl MemoryPermissions {
+    /// The security state(s) this region may be accessed from.
+    ///
+    /// Non-secure callable (`c`) regions are secure memory, so count as secure.
+    pub fn security(&self) -> SecurityState {
+        match (self.secure || self.non_secure_callable, self.non_secure) {
+            (true, true) => SecurityState::Both,
+            (true, false) => SecurityState::Secure,
+            (false, true) => SecurityState::NonSecure,
+            (false, false) => SecurityState::Unspecified,
+        }
+    }
+
+    /// The permissions granted by either `self` or `other`.
+    pub fn union(&self, other: &Self) -> Self {
+        MemoryPermissions {
+            read: self.read || other.read,
+            write: self.write || other.write,
+            execute: self.execute || other.execute,
+            peripheral: self.peripheral || other.peripheral,
+            secure: self.secure || other.secure,
+            non_secure: self.non_secure || other.non_secure,
+            non_secure_callable: self.non_secure_callable || other.non_secure_callable,
+        }
+    }
+}
+
+impl fmt::Display for MemoryPermissions {
+    /// Formats the permissions as a PDSC `access` attribute, e.g. `rwx`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let flags = [
+            (self.read, 'r'),
+            (self.write, 'w'),
+            (self.execute, 'x'),
+            (self.peripheral, 'p'),
+            (self.secure, 's'),
+            (self.non_secure, 'n'),
+            (self.non_secure_callable, 'c'),
+        ];
+        for (set, c) in flags {
+            if set {
+                write!(f, "{}", c)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Formats a byte count with binary units, e.g. `1536` as `1.5 KB`.
+///
+/// As is conventional for CMSIS tooling, a `KB` is 1024 bytes, an `MB` is 1024 `KB` and so on.
+/// Fractional values are rounded to at most two decimal places.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 6] = ["KB", "MB", "GB", "TB", "PB", "EB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut unit = 0;
+    let mut scale: u64 = 1024;
+    while unit + 1 < UNITS.len() && bytes / scale >= 1024 {
+        scale *= 1024;
+        unit += 1;
+    }
+    let value = format!("{:.2}", bytes as f64 / scale as f64);
+    let value = value.trim_end_matches('0').trim_end_matches('.');
+    format!("{} {}", value, UNITS[unit])
+}
+
 enum NumberBool {
     False,
     True,
@@ -453,25 +1174,270 @@ impl FromStr for NumberBool {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct Memory {
+    /// The `id` or `name` of the region, which is also its key in [`Memories`].
+    #[serde(default)]
+    pub name: String,
     pub p_name: Option<String>,
+    /// The unit of a multi-unit processor the region is scoped to, from `Punit`.
+    pub p_unit: Option<usize>,
+    /// The id of the condition the region is present under, from `condition`, see
+    /// [`Device::applies`].
+    #[serde(default)]
+    pub condition: Option<String>,
     pub access: MemoryPermissions,
     pub start: u64,
     pub size: u64,
     pub startup: bool,
     pub default: bool,
+    /// Attributes of `<memory>` this crate doesn't model, when captured with
+    /// [`ParseOptions::capture_extra`].
+    #[serde(default)]
+    pub extra_attributes: HashMap<String, String>,
+}
+
+impl Memory {
+    /// A region that is neither `startup` nor `default`, with `access` permissions written as in
+    /// a PDSC, e.g. `rx`.
+    pub fn new(name: impl Into<String>, access: &str, start: u64, size: u64) -> Self {
+        Memory {
+            name: name.into(),
+            p_name: None,
+            p_unit: None,
+            condition: None,
+            access: MemoryPermissions::from_str(access),
+            start,
+            size,
+            startup: false,
+            default: false,
+            extra_attributes: HashMap::new(),
+        }
+    }
+
+    /// The size of this region formatted with binary units, e.g. `1 MB`.
+    pub fn size_human(&self) -> String {
+        format_bytes(self.size)
+    }
+
+    /// The exclusive end address of this region, `start + size`.
+    ///
+    /// A region reaching the top of the 64-bit address space saturates to `u64::MAX`, so its
+    /// last byte is not covered by `start..end`.
+    pub fn end(&self) -> u64 {
+        self.start.saturating_add(self.size)
+    }
+}
+
+/// Serializes the region along with its computed exclusive [`Memory::end`].
+impl Serialize for Memory {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Memory", 11)?;
+        state.serialize_field("name", &self.name)?;
+        state.serialize_field("p_name", &self.p_name)?;
+        match self.p_unit {
+            Some(unit) => state.serialize_field("p_unit", &unit)?,
+            None => state.skip_field("p_unit")?,
+        }
+        match &self.condition {
+            Some(condition) => state.serialize_field("condition", condition)?,
+            None => state.skip_field("condition")?,
+        }
+        state.serialize_field("access", &self.access)?;
+        state.serialize_field("start", &self.start)?;
+        state.serialize_field("size", &self.size)?;
+        state.serialize_field("end", &self.end())?;
+        state.serialize_field("startup", &self.startup)?;
+        state.serialize_field("default", &self.default)?;
+        if self.extra_attributes.is_empty() {
+            state.skip_field("extra_attributes")?;
+        } else {
+            state.serialize_field("extra_attributes", &self.extra_attributes)?;
+        }
+        state.end()
+    }
+}
+
+/// What a memory region is used for, in the order regions are conventionally listed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum MemoryKind {
+    Rom,
+    Ram,
+    /// Tightly coupled memory, such as `ITCM` or `DTCM`.
+    Tcm,
+    Peripheral,
+    /// A region without any access permissions.
+    Other,
+}
+
+/// A memory region id with a conventional meaning across vendors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum WellKnownMemory {
+    /// `IROM1`, the main on-chip ROM in the CMSIS naming scheme.
+    Irom1,
+    /// `IROM2`, the secondary on-chip ROM.
+    Irom2,
+    /// `IRAM1`, the main on-chip RAM.
+    Iram1,
+    /// `IRAM2`, the secondary on-chip RAM.
+    Iram2,
+    /// The main code flash, e.g. `FLASH`, `PROGRAM_FLASH` or `PFLASH`.
+    ProgramFlash,
+    /// Flash intended for data, e.g. `DATA_FLASH` or `DFLASH`.
+    DataFlash,
+    /// The main SRAM, e.g. `SRAM` or `RAM`.
+    Sram,
+    Eeprom,
+    /// One-time programmable memory.
+    Otp,
+}
+
+impl WellKnownMemory {
+    /// Returns true for the ids that name a device's main flash.
+    pub fn is_main_flash(&self) -> bool {
+        matches!(self, WellKnownMemory::Irom1 | WellKnownMemory::ProgramFlash)
+    }
+
+    /// Returns true for the ids that name a device's main RAM.
+    pub fn is_main_ram(&self) -> bool {
+        matches!(self, WellKnownMemory::Iram1 | WellKnownMemory::Sram)
+    }
+}
+
+impl Memory {
+    /// Recognizes this region's id as one of the common names in [`WellKnownMemory`].
+    ///
+    /// This is a best-effort match on the whole id, ignoring case; numbered banks such as
+    /// `SRAM1` or vendor specific ids are not recognized.
+    pub fn well_known(&self) -> Option<WellKnownMemory> {
+        match self.name.to_ascii_uppercase().as_str() {
+            "IROM1" => Some(WellKnownMemory::Irom1),
+            "IROM2" => Some(WellKnownMemory::Irom2),
+            "IRAM1" => Some(WellKnownMemory::Iram1),
+            "IRAM2" => Some(WellKnownMemory::Iram2),
+            "FLASH" | "PROGRAM_FLASH" | "PFLASH" | "CODE_FLASH" => {
+                Some(WellKnownMemory::ProgramFlash)
+            }
+            "DATA_FLASH" | "DFLASH" => Some(WellKnownMemory::DataFlash),
+            "SRAM" | "RAM" => Some(WellKnownMemory::Sram),
+            "EEPROM" => Some(WellKnownMemory::Eeprom),
+            "OTP" => Some(WellKnownMemory::Otp),
+            _ => None,
+        }
+    }
+
+    /// Classifies this region by its permissions, or by its name for tightly coupled memory.
+    pub fn kind(&self) -> MemoryKind {
+        if self.access.peripheral {
+            MemoryKind::Peripheral
+        } else if self.name.to_ascii_uppercase().contains("TCM") {
+            MemoryKind::Tcm
+        } else if self.access.write {
+            MemoryKind::Ram
+        } else if self.access.read || self.access.execute {
+            MemoryKind::Rom
+        } else {
+            MemoryKind::Other
+        }
+    }
+
+    /// Returns true if code can be executed from this region.
+    ///
+    /// Besides the `x` permission, a non-peripheral region is assumed to be executable when its
+    /// name marks it as ROM, flash, execute-in-place (`XIP`, `QSPI`, `OSPI`) or tightly coupled
+    /// memory, since packs often declare external flash and TCMs as plain `rw`.
+    pub fn is_executable(&self) -> bool {
+        self.access.execute
+            || (!self.access.peripheral && (self.is_rom_like() || self.kind() == MemoryKind::Tcm))
+    }
+
+    /// Returns true if this region is executable and holds non-volatile code, i.e. it is either
+    /// read-only or named like a ROM. Peripheral regions never hold code.
+    pub(super) fn is_flash(&self) -> bool {
+        !self.access.peripheral
+            && self.is_executable()
+            && (!self.access.write || self.is_rom_like())
+    }
+
+    /// Returns true if this region is non-volatile memory a flash algorithm may program: flash,
+    /// EEPROM, OTP or other ROM, as opposed to volatile RAM, TCM or peripherals. Unlike flash,
+    /// an EEPROM isn't taken to hold code.
+    ///
+    /// A region named like non-volatile memory is taken as such whatever its permissions, since
+    /// packs often declare flash `rw`; otherwise a region is non-volatile if it is readable or
+    /// executable but not writable.
+    pub fn is_nvm(&self) -> bool {
+        if self.access.peripheral || self.kind() == MemoryKind::Tcm {
+            return false;
+        }
+        let name = self.name.to_ascii_uppercase();
+        self.is_rom_like()
+            || ["EEPROM", "OTP", "NVM"]
+                .iter()
+                .any(|marker| name.contains(marker))
+            || (!self.access.write && (self.access.read || self.access.execute))
+    }
+
+    /// Returns true if this region is named like memory that holds code. `EEPROM` holds data,
+    /// so it doesn't count as `ROM`.
+    fn is_rom_like(&self) -> bool {
+        let name = self.name.to_ascii_uppercase().replace("EEPROM", "");
+        ["ROM", "FLASH", "XIP", "QSPI", "OSPI"]
+            .iter()
+            .any(|marker| name.contains(marker))
+    }
+
+    /// Returns true if this region is visible to the processor with the given `Pname`.
+    ///
+    /// A region without a `Pname` is visible to every processor. `Pname` is compared ignoring
+    /// ASCII case, as for [`Debug::applies_to`].
+    pub fn applies_to(&self, name: Option<&str>) -> bool {
+        self.p_name.as_deref().map_or(true, |n| {
+            name.is_some_and(|name| n.eq_ignore_ascii_case(name))
+        })
+    }
+
+    /// Returns true if this region is visible to `processor`, respecting both `Pname` and
+    /// `Punit`.
+    pub fn applies_to_processor(&self, processor: &Processor) -> bool {
+        self.applies_to(processor.name.as_deref())
+            && self.p_unit.map_or(true, |unit| unit == processor.unit)
+    }
+}
+
+impl fmt::Display for Memory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:#010x}..{:#010x} ({}, {})",
+            self.start,
+            self.end(),
+            self.size_human(),
+            self.access
+        )
+    }
 }
 
 struct MemElem(String, Memory);
 
 impl FromElem for MemElem {
     fn from_elem(e: &Node) -> Result<Self, Error> {
+        Self::from_elem_with(e, &ParseOptions::default())
+    }
+
+    fn from_elem_with(e: &Node, opts: &ParseOptions) -> Result<Self, Error> {
+        // Without `access`, an explicit `type` is trusted over the conventional ids, which
+        // merely contain `ROM` or `RAM`.
         let access = MemoryPermissions::from_str(e.attribute("access").unwrap_or_else(|| {
-            let memtype = e.attribute("id").unwrap_or_default();
-            if memtype.contains("ROM") {
+            let memtype = e.attribute("type").unwrap_or_default();
+            let id = e.attribute("id").unwrap_or_default();
+            if memtype.eq_ignore_ascii_case("ROM") {
+                "rx"
+            } else if memtype.eq_ignore_ascii_case("RAM") {
+                "rw"
+            } else if id.contains("ROM") {
                 "rx"
-            } else if memtype.contains("RAM") {
+            } else if id.contains("RAM") {
                 "rw"
             } else {
                 ""
@@ -483,8 +1449,10 @@ impl FromElem for MemElem {
             .map(|s| s.to_string())
             .ok_or_else(|| format_err!("No name found for memory"))?;
         let p_name = e.attribute("Pname").map(|s| s.to_string());
-        let start = attr_parse_hex(e, "start")?;
-        let size = attr_parse_hex(e, "size")?;
+        let p_unit = attr_parse(e, "Punit").ok();
+        let condition = e.attribute("condition").map(|s| s.to_string());
+        let start = attr_parse_size(e, "start")?;
+        let size = attr_parse_size(e, "size")?;
         let startup = attr_parse(e, "startup")
             .map(|nb: NumberBool| nb.into())
             .unwrap_or_default();
@@ -492,71 +1460,280 @@ impl FromElem for MemElem {
             .map(|nb: NumberBool| nb.into())
             .unwrap_or_default();
         Ok(MemElem(
-            name,
+            name.clone(),
             Memory {
+                name,
                 p_name,
+                p_unit,
+                condition,
                 access,
                 start,
                 size,
                 startup,
                 default,
+                extra_attributes: extra_attributes(
+                    e,
+                    &[
+                        "id",
+                        "name",
+                        "access",
+                        "type",
+                        "Pname",
+                        "Punit",
+                        "condition",
+                        "start",
+                        "size",
+                        "startup",
+                        "default",
+                    ],
+                    opts,
+                ),
             },
         ))
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct Memories(pub HashMap<String, Memory>);
 
-fn merge_memories(lhs: Memories, rhs: &Memories) -> Memories {
-    let rhs: Vec<_> = rhs
-        .0
-        .iter()
-        .filter_map(|(k, v)| {
-            if lhs.0.contains_key(k) {
-                None
-            } else {
-                Some((k.clone(), v.clone()))
+impl Memories {
+    /// The `<memory>` children of `e`, reporting any that fail to parse to `diags`.
+    pub(crate) fn from_children(e: &Node, diags: &mut dyn Diagnostics) -> Memories {
+        e.children()
+            .filter(|c| c.has_tag_name("memory"))
+            .flat_map(|c| MemElem::from_elem(&c).ok_report(&c, diags))
+            .map(|MemElem(name, mem)| (name, mem))
+            .collect()
+    }
+
+    /// The regions whose permissions satisfy `pred`, sorted by address.
+    pub fn by_access<F>(&self, pred: F) -> Vec<&Memory>
+    where
+        F: Fn(&MemoryPermissions) -> bool,
+    {
+        self.filtered(|m| pred(&m.access))
+    }
+
+    /// The writable regions other than peripherals, as counted by [`Device::total_ram`].
+    pub fn writable(&self) -> Vec<&Memory> {
+        self.by_access(|access| access.write && !access.peripheral)
+    }
+
+    /// The regions code can be executed from, see [`Memory::is_executable`].
+    pub fn executable(&self) -> Vec<&Memory> {
+        self.filtered(Memory::is_executable)
+    }
+
+    /// The peripheral regions.
+    pub fn peripherals(&self) -> Vec<&Memory> {
+        self.by_access(|access| access.peripheral)
+    }
+
+    /// The regions other than peripherals: those that may appear in a linker script or be
+    /// programmed.
+    pub fn non_peripheral(&self) -> Vec<&Memory> {
+        self.by_access(|access| !access.peripheral)
+    }
+
+    fn filtered<F>(&self, pred: F) -> Vec<&Memory>
+    where
+        F: Fn(&Memory) -> bool,
+    {
+        let mut regions: Vec<&Memory> = self.0.values().filter(|m| pred(m)).collect();
+        regions.sort_by_key(|m| (m.start, &m.name));
+        regions
+    }
+
+    /// Coalesces contiguous regions with the same permissions and `Pname` into one.
+    ///
+    /// Regions are joined when one starts exactly where the previous one ends. The joined region
+    /// keeps the id of the lowest region, and is `startup` or `default` if any part of it was.
+    /// The regions are left untouched; a new set of regions is returned.
+    pub fn merge_adjacent(&self) -> Memories {
+        let mut regions: Vec<(&String, &Memory)> = self.0.iter().collect();
+        regions.sort_by_key(|(name, mem)| (mem.start, *name));
+        let mut merged: Vec<(String, Memory)> = Vec::with_capacity(regions.len());
+        for (name, mem) in regions {
+            if let Some((_, last)) = merged.last_mut() {
+                if last.start.checked_add(last.size) == Some(mem.start)
+                    && last.access == mem.access
+                    && last.p_name == mem.p_name
+                {
+                    last.size = last.size.saturating_add(mem.size);
+                    last.startup |= mem.startup;
+                    last.default |= mem.default;
+                    continue;
+                }
             }
-        })
-        .collect();
-    let mut lhs = lhs;
-    lhs.0.extend(rhs);
-    lhs
+            merged.push((name.clone(), mem.clone()));
+        }
+        merged.into_iter().collect()
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum AlgorithmStyle {
-    Keil,
-    IAR,
-    CMSIS,
+impl IntoIterator for Memories {
+    type Item = (String, Memory);
+    type IntoIter = std::collections::hash_map::IntoIter<String, Memory>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
 }
 
-impl FromStr for AlgorithmStyle {
-    type Err = Error;
-    fn from_str(from: &str) -> Result<Self, Error> {
-        match from {
-            "Keil" => Ok(AlgorithmStyle::Keil),
-            "IAR" => Ok(AlgorithmStyle::IAR),
-            "CMSIS" => Ok(AlgorithmStyle::CMSIS),
-            unknown => Err(format_err!("Unknown algorithm style {}", unknown)),
-        }
+impl<'a> IntoIterator for &'a Memories {
+    type Item = (&'a String, &'a Memory);
+    type IntoIter = std::collections::hash_map::Iter<'a, String, Memory>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Algorithm {
-    pub file_name: PathBuf,
+impl FromIterator<(String, Memory)> for Memories {
+    fn from_iter<I: IntoIterator<Item = (String, Memory)>>(iter: I) -> Self {
+        Memories(iter.into_iter().collect())
+    }
+}
+
+/// Adds regions, replacing any existing region with the same id.
+impl Extend<(String, Memory)> for Memories {
+    fn extend<I: IntoIterator<Item = (String, Memory)>>(&mut self, iter: I) {
+        self.0.extend(iter)
+    }
+}
+
+/// Adds the regions of the parent `rhs` to those of the more specific `lhs`. A region both
+/// declare keeps the address range and flags of `lhs`, but may be accessed as either allows.
+fn merge_memories(mut lhs: Memories, rhs: &Memories) -> Memories {
+    for (name, parent) in &rhs.0 {
+        match lhs.0.entry(name.clone()) {
+            Entry::Occupied(mut entry) => {
+                let memory = entry.get_mut();
+                memory.access = memory.access.union(&parent.access);
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(parent.clone());
+            }
+        }
+    }
+    lhs
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum AlgorithmStyle {
+    Keil,
+    IAR,
+    CMSIS,
+}
+
+impl FromStr for AlgorithmStyle {
+    type Err = Error;
+    fn from_str(from: &str) -> Result<Self, Error> {
+        match from {
+            "Keil" => Ok(AlgorithmStyle::Keil),
+            "IAR" => Ok(AlgorithmStyle::IAR),
+            "CMSIS" => Ok(AlgorithmStyle::CMSIS),
+            unknown => Err(format_err!("Unknown algorithm style {}", unknown)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Algorithm {
+    pub file_name: PathBuf,
     pub start: u64,
     pub size: u64,
     pub default: bool,
     pub ram_start: Option<u64>,
     pub ram_size: Option<u64>,
     pub style: AlgorithmStyle,
+    /// The processor that runs the algorithm, from `Pname`.
+    #[serde(default)]
+    pub p_name: Option<String>,
+    /// The id of the condition the algorithm is used under, from `condition`, see
+    /// [`Device::applies`].
+    #[serde(default)]
+    pub condition: Option<String>,
+}
+
+impl Algorithm {
+    /// Returns true if the address range programmed by this algorithm contains all of `region`.
+    ///
+    /// Both ranges are half open, `start..start + size`, so an algorithm covers a region that
+    /// ends exactly where the algorithm does, but not one that merely overlaps it.
+    pub fn covers(&self, region: &Memory) -> bool {
+        let end = self.start.saturating_add(self.size);
+        self.start <= region.start && region.end() <= end
+    }
+
+    /// Returns true if this algorithm is marked `default` and [covers](Self::covers) `region`.
+    pub fn is_default_for(&self, region: &Memory) -> bool {
+        self.default && self.covers(region)
+    }
+
+    /// The RAM window, as `(start, size)`, this algorithm is loaded into and runs from.
+    ///
+    /// This is `RAMstart` and `RAMsize` when the pack gives both. Otherwise it is the largest
+    /// writable region of `memories` marked `default`, preferring one scoped to the algorithm's
+    /// `Pname`. Regions scoped to another processor are never chosen.
+    pub fn resolve_ram(&self, memories: &Memories) -> Option<(u64, u64)> {
+        if let (Some(start), Some(size)) = (self.ram_start, self.ram_size) {
+            return Some((start, size));
+        }
+        let own = |m: &Memory| self.p_name.is_some() && m.p_name == self.p_name;
+        memories
+            .0
+            .values()
+            .filter(|m| m.access.write && !m.access.peripheral && m.default)
+            .filter(|m| self.p_name.is_none() || m.p_name.is_none() || own(m))
+            .max_by_key(|m| (own(m), m.size, Reverse(m.start)))
+            .map(|m| (m.start, m.size))
+    }
+
+    /// The path of this algorithm within the pack extracted to `pack_root`.
+    ///
+    /// `file_name` may be separated with `\` or `/`, whatever the platform. Its `.` and `..`
+    /// segments are resolved without looking at the file system, and a name that leads out of
+    /// `pack_root`, or isn't relative to it at all, is an error.
+    pub fn resolve_path(&self, pack_root: &Path) -> Result<PathBuf, Error> {
+        resolve(pack_root, &self.file_name).map_err(|e| format_err!("algorithm {}", e))
+    }
+}
+
+/// A flash region of a device and the algorithm that programs it, see [`Device::flash_banks`].
+#[derive(Debug, Clone, Serialize)]
+pub struct FlashBank {
+    pub region: Memory,
+    /// The [primary algorithm](Device::primary_algorithm) for the region, if any covers it.
+    pub algorithm: Option<Algorithm>,
+}
+
+/// Just what a flasher needs to program a device, see [`Device::flash_profile`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FlashProfile {
+    /// The flash regions, sorted by address.
+    pub regions: Vec<Memory>,
+    /// The algorithms programming `regions`, each listed once, in the order of the first region
+    /// they program.
+    pub algorithms: Vec<Algorithm>,
+    /// The RAM algorithms are loaded into when they don't name their own.
+    pub ram: Memory,
+}
+
+impl FlashProfile {
+    /// This profile as compact JSON.
+    pub fn to_json(&self) -> String {
+        // Like a `Device`, a profile has no maps with non-string keys.
+        serde_json::to_string(self).expect("flash profiles always serialize to JSON")
+    }
 }
 
 impl FromElem for Algorithm {
     fn from_elem(e: &Node) -> Result<Self, Error> {
+        Self::from_elem_with(e, &ParseOptions::default())
+    }
+
+    fn from_elem_with(e: &Node, opts: &ParseOptions) -> Result<Self, Error> {
         let default = attr_parse(e, "default")
             .map(|nb: NumberBool| nb.into())
             .unwrap_or_default();
@@ -564,13 +1741,15 @@ impl FromElem for Algorithm {
         let file_name: &str = attr_map(e, "name")?;
         let style = attr_parse(e, "style").ok().unwrap_or(AlgorithmStyle::Keil);
         Ok(Self {
-            file_name: file_name.replace('\\', "/").into(),
-            start: attr_parse_hex(e, "start")?,
-            size: attr_parse_hex(e, "size")?,
-            ram_start: attr_parse_hex(e, "RAMstart").ok(),
-            ram_size: attr_parse_hex(e, "RAMsize").ok(),
+            file_name: opts.path_style.normalize(file_name).into(),
+            start: attr_parse_size(e, "start")?,
+            size: attr_parse_size(e, "size")?,
+            ram_start: attr_parse_size(e, "RAMstart").ok(),
+            ram_size: attr_parse_size(e, "RAMsize").ok(),
             default,
             style,
+            p_name: attr_parse(e, "Pname").ok(),
+            condition: attr_parse(e, "condition").ok(),
         })
     }
 }
@@ -585,21 +1764,468 @@ struct DeviceBuilder {
     vendor: Option<String>,
     family: Option<String>,
     sub_family: Option<String>,
+    base_device: Option<String>,
+    condition: Option<String>,
+    description: Option<String>,
+    extra_attributes: HashMap<String, String>,
+    variants: Vec<String>,
+    silicon_revision: Option<String>,
+    /// Inherited algorithms dropped by an `<algorithm name="..." remove="1"/>` element here or in
+    /// an enclosing element, by file name.
+    removed_algorithms: Vec<PathBuf>,
+}
+
+/// Splits the silicon revision off a `Dname` written as `STM32F407@B`.
+fn split_revision(name: &str) -> (String, Option<String>) {
+    match name.split_once('@') {
+        Some((base, revision)) if !base.is_empty() && !revision.is_empty() => {
+            (base.to_string(), Some(revision.to_string()))
+        }
+        _ => (name.to_string(), None),
+    }
+}
+
+/// Strips the `:id` suffix from a `Dvendor` value, e.g. `STMicroelectronics:13`.
+pub(crate) fn strip_vendor_id(vendor: &str) -> &str {
+    match vendor.rsplit_once(':') {
+        Some((name, id)) if !id.is_empty() && id.bytes().all(|b| b.is_ascii_digit()) => name.trim(),
+        _ => vendor.trim(),
+    }
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, PartialEq, Serialize)]
 pub struct Device {
     pub name: String,
     pub memories: Memories,
+    /// The flash algorithms of the device and those inherited from its enclosing elements,
+    /// sorted by `start`, with a `default` algorithm ahead of others at the same `start`. An
+    /// algorithm declared at several levels with the same name, start and size is listed once,
+    /// as declared by the innermost element. An element may drop an inherited algorithm with the
+    /// non-standard `<algorithm name="..." remove="1"/>`, naming the algorithm to drop.
     pub algorithms: Vec<Algorithm>,
     pub processors: Vec<Processor>,
     pub vendor: Option<String>,
     pub family: String,
     pub sub_family: Option<String>,
+    /// The `Dname` of the device this one is a `<variant>` of, if any.
+    pub base_device: Option<String>,
+    /// The id of the condition gating this device, from a `condition` attribute on the device
+    /// or one of its enclosing elements.
+    pub condition: Option<String>,
+    /// The `<description>` of the device, or of the closest enclosing element that has one,
+    /// with whitespace collapsed.
+    pub description: Option<String>,
+    /// Attributes of the device and its enclosing elements this crate doesn't model, when
+    /// captured with [`ParseOptions::capture_extra`]. The innermost element wins.
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub extra_attributes: HashMap<String, String>,
+    /// The latest release of the pack this device was read from, when parsed as part of a
+    /// [`Pack`](crate::pdsc::Pack) or merged with [`Devices::merge_preferring`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pack_version: Option<String>,
+    /// The names of the `<variant>`s collapsed into this device, when parsed with
+    /// [`ParseOptions::collapse_variants`]; empty otherwise.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub variants: Vec<String>,
+    /// The silicon revision some packs append to `Dname`, `B` for `STM32F407@B`. `name` holds
+    /// the name without it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub silicon_revision: Option<String>,
+}
+
+impl Device {
+    /// A hash of the name, memories, algorithms and processors of this device, for telling
+    /// whether a device changed between two pack versions.
+    ///
+    /// The hash does not depend on the order memories were declared in, and is the same on every
+    /// run of the same build; it is not meant to be persisted across versions of this crate.
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = StableHasher::default();
+        self.name.hash(&mut hasher);
+        self.silicon_revision.hash(&mut hasher);
+        let mut memories: Vec<(&String, &Memory)> = self.memories.0.iter().collect();
+        memories.sort_by_key(|(name, _)| *name);
+        for (name, memory) in memories {
+            name.hash(&mut hasher);
+            memory.p_name.hash(&mut hasher);
+            memory.p_unit.hash(&mut hasher);
+            memory.condition.hash(&mut hasher);
+            memory.access.hash(&mut hasher);
+            memory.start.hash(&mut hasher);
+            memory.size.hash(&mut hasher);
+            memory.startup.hash(&mut hasher);
+            memory.default.hash(&mut hasher);
+        }
+        for algorithm in &self.algorithms {
+            algorithm.file_name.hash(&mut hasher);
+            algorithm.start.hash(&mut hasher);
+            algorithm.size.hash(&mut hasher);
+            algorithm.default.hash(&mut hasher);
+            algorithm.ram_start.hash(&mut hasher);
+            algorithm.ram_size.hash(&mut hasher);
+            algorithm.style.hash(&mut hasher);
+            algorithm.p_name.hash(&mut hasher);
+            algorithm.condition.hash(&mut hasher);
+        }
+        for processor in &self.processors {
+            processor.core.hash(&mut hasher);
+            processor.core_revision.hash(&mut hasher);
+            processor.fpu.hash(&mut hasher);
+            processor.mpu.hash(&mut hasher);
+            processor.mpu_regions.hash(&mut hasher);
+            processor.tz.hash(&mut hasher);
+            processor.endian.hash(&mut hasher);
+            processor.ap.hash(&mut hasher);
+            processor.dp.hash(&mut hasher);
+            processor.address.hash(&mut hasher);
+            processor.svd.hash(&mut hasher);
+            processor.name.hash(&mut hasher);
+            processor.unit.hash(&mut hasher);
+            processor.default_reset_sequence.hash(&mut hasher);
+            processor.targetsel.hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    /// The memory regions visible to `processor`, ordered by start address.
+    ///
+    /// A region scoped by `Pname` is visible to the processors of that name only, and one also
+    /// scoped by `Punit` to that unit only.
+    pub fn memories_for_processor(&self, processor: &Processor) -> Vec<&Memory> {
+        let mut memories: Vec<&Memory> = self
+            .memories
+            .0
+            .values()
+            .filter(|m| m.applies_to_processor(processor))
+            .collect();
+        memories.sort_by_key(|m| (m.start, &m.name));
+        memories
+    }
+
+    /// The index of `processor` in [`Device::processors`], the index tools like probe-rs refer
+    /// to cores by.
+    ///
+    /// Processors are identified by their `Pname` and `Punit`, so a copy of one of this device's
+    /// processors is found even if it has since been changed otherwise.
+    pub fn processor_index(&self, processor: &Processor) -> Option<usize> {
+        self.processors
+            .iter()
+            .position(|p| p.name == processor.name && p.unit == processor.unit)
+    }
+
+    /// The processor that boots this device, see [`Processor::is_boot_core`].
+    pub fn boot_core(&self) -> Option<&Processor> {
+        self.processors.iter().find(|p| p.is_boot_core)
+    }
+
+    /// The vendor name without the `:id` suffix of `Dvendor`, e.g. `STMicroelectronics` for
+    /// `STMicroelectronics:13`.
+    pub fn vendor_name(&self) -> Option<&str> {
+        self.vendor.as_deref().map(strip_vendor_id)
+    }
+
+    /// Total size of the flash regions of this device: the executable regions that are
+    /// read-only or named like a ROM, see [`Memory::is_executable`].
+    pub fn total_flash(&self) -> u64 {
+        self.memories
+            .0
+            .values()
+            .filter(|m| m.is_flash())
+            .map(|m| m.size)
+            .fold(0, u64::saturating_add)
+    }
+
+    /// The region `processor` boots from: the `startup` region visible to it, respecting both
+    /// `Pname` and `Punit`.
+    ///
+    /// A region scoped to the processor's `Pname` wins over an unscoped one; if there are still
+    /// several, the lowest addressed is chosen.
+    pub fn startup_memory(&self, processor: &Processor) -> Option<&Memory> {
+        self.memories
+            .0
+            .values()
+            .filter(|m| m.startup && m.applies_to_processor(processor))
+            .min_by_key(|m| (m.p_name.is_none(), m.start))
+    }
+
+    /// The flash region code is loaded into by default: the `startup` region if there is one,
+    /// then the `default` region, then the lowest addressed flash region.
+    pub fn default_rom(&self) -> Option<&Memory> {
+        self.memories
+            .0
+            .values()
+            .filter(|m| m.is_flash())
+            .min_by_key(|m| (!m.startup, !m.default, m.start))
+    }
+
+    /// The RAM region data is placed in by default: the `default` writable region if there is
+    /// one, then the lowest addressed writable region. Peripheral regions are never chosen.
+    pub fn default_ram(&self) -> Option<&Memory> {
+        self.memories
+            .0
+            .values()
+            .filter(|m| m.access.write && !m.access.peripheral)
+            .min_by_key(|m| (!m.default, m.start))
+    }
+
+    /// The regions of this device's memory map, sorted by address. Peripheral regions are left
+    /// out.
+    pub fn memory_map(&self) -> Vec<&Memory> {
+        self.memories.non_peripheral()
+    }
+
+    /// Total size of the writable regions of this device, other than peripherals.
+    pub fn total_ram(&self) -> u64 {
+        self.memories
+            .0
+            .values()
+            .filter(|m| m.access.write && !m.access.peripheral)
+            .map(|m| m.size)
+            .fold(0, u64::saturating_add)
+    }
+
+    /// The memories of this device grouped by [`MemoryKind`], in kind order with each group
+    /// sorted by address. Kinds without any regions are left out.
+    pub fn memories_by_kind(&self) -> Vec<(MemoryKind, Vec<&Memory>)> {
+        let mut memories: Vec<&Memory> = self.memories.0.values().collect();
+        memories.sort_by_key(|m| (m.kind(), m.start, &m.name));
+        let mut groups: Vec<(MemoryKind, Vec<&Memory>)> = Vec::new();
+        for memory in memories {
+            match groups.last_mut() {
+                Some((kind, group)) if *kind == memory.kind() => group.push(memory),
+                _ => groups.push((memory.kind(), vec![memory])),
+            }
+        }
+        groups
+    }
+
+    /// The algorithm to use when programming `region`.
+    ///
+    /// Of the algorithms that cover the region, the one chosen is, in order of preference:
+    ///
+    /// 1. of [`AlgorithmStyle::Keil`], since most flashers only support `.FLM` files, even
+    ///    over a `default` algorithm of another style,
+    /// 2. marked `default`,
+    /// 3. starting lowest, as [`Device::algorithms`] is sorted by `start`,
+    /// 4. declared by the innermost element.
+    ///
+    /// A warning is logged when more than one default algorithm of the chosen style covers the
+    /// region.
+    ///
+    /// Volatile regions, those that aren't [`Memory::is_nvm`], never get an algorithm; a warning
+    /// is logged if one covers them anyway.
+    pub fn primary_algorithm(&self, region: &Memory) -> Option<&Algorithm> {
+        if !region.is_nvm() {
+            if self.algorithms.iter().any(|alg| alg.covers(region)) {
+                log::warn!(
+                    "{}: not programming volatile region {} with a flash algorithm",
+                    self.name,
+                    region.name
+                );
+            }
+            return None;
+        }
+        let rank = |alg: &Algorithm| (!matches!(alg.style, AlgorithmStyle::Keil), !alg.default);
+        let mut candidates: Vec<&Algorithm> = self
+            .algorithms
+            .iter()
+            .filter(|alg| alg.covers(region))
+            .collect();
+        candidates.sort_by_key(|alg| rank(alg));
+        let primary = *candidates.first()?;
+        if let Some(other) = candidates
+            .get(1)
+            .filter(|other| other.default && rank(other) == rank(primary))
+        {
+            log::warn!(
+                "{}: multiple default algorithms cover {:#010x}, using {:?} over {:?}",
+                self.name,
+                region.start,
+                primary.file_name,
+                other.file_name,
+            );
+        }
+        Some(primary)
+    }
+
+    /// The flash regions of this device, sorted by address, each with the algorithm that
+    /// programs it.
+    ///
+    /// A flash region is an executable region that is read-only or named like a ROM, as counted
+    /// by [`total_flash`](Self::total_flash).
+    pub fn flash_banks(&self) -> Vec<FlashBank> {
+        let mut regions: Vec<&Memory> = self.memories.0.values().filter(|m| m.is_flash()).collect();
+        regions.sort_by_key(|m| (m.start, &m.name));
+        regions
+            .into_iter()
+            .map(|region| FlashBank {
+                region: region.clone(),
+                algorithm: self.primary_algorithm(region).cloned(),
+            })
+            .collect()
+    }
+
+    /// The part of this device a flasher needs: its flash regions, the [primary
+    /// algorithm](Self::primary_algorithm) of each, and the [default RAM](Self::default_ram).
+    ///
+    /// Returns `None` if the device has no RAM to run an algorithm from.
+    pub fn flash_profile(&self) -> Option<FlashProfile> {
+        let ram = self.default_ram()?.clone();
+        let mut regions = Vec::new();
+        let mut algorithms: Vec<Algorithm> = Vec::new();
+        for bank in self.flash_banks() {
+            if let Some(algorithm) = bank.algorithm {
+                if !algorithms.contains(&algorithm) {
+                    algorithms.push(algorithm);
+                }
+            }
+            regions.push(bank.region);
+        }
+        Some(FlashProfile {
+            regions,
+            algorithms,
+            ram,
+        })
+    }
+
+    /// The secure view of the non-secure region `region` on an Armv8-M part with TrustZone.
+    ///
+    /// Such parts map the same memory twice, at addresses that differ in a single bit (bit 26
+    /// on STM32L5, bit 28 on LPC55S69). The alias is the secure region of the same size whose
+    /// start differs from that of `region` in exactly one bit.
+    pub fn secure_alias(&self, region: &Memory) -> Option<&Memory> {
+        if region.access.security() != SecurityState::NonSecure {
+            return None;
+        }
+        self.memories.0.values().find(|m| {
+            m.access.security() == SecurityState::Secure
+                && m.size == region.size
+                && (m.start ^ region.start).count_ones() == 1
+        })
+    }
+
+    /// The algorithm to program the non-secure region `region` with, through its
+    /// [secure alias](Self::secure_alias); flashers must program TrustZone parts through the
+    /// secure view.
+    ///
+    /// For a region that is not non-secure, this is its own
+    /// [`primary_algorithm`](Self::primary_algorithm).
+    pub fn secure_flash_algorithm(&self, region: &Memory) -> Option<&Algorithm> {
+        match region.access.security() {
+            SecurityState::NonSecure => self
+                .secure_alias(region)
+                .and_then(|alias| self.primary_algorithm(alias)),
+            _ => self.primary_algorithm(region),
+        }
+    }
+
+    /// The distinct cores of this device's processors, in declaration order.
+    ///
+    /// A processor declared with the `*` wildcard core is only listed, as [`Core::Any`], when no
+    /// processor has a specific core.
+    pub fn cores(&self) -> Vec<Core> {
+        let mut cores: Vec<Core> = Vec::new();
+        for processor in &self.processors {
+            if !cores.contains(&processor.core) {
+                cores.push(processor.core.clone());
+            }
+        }
+        if cores.len() > 1 {
+            cores.retain(|core| *core != Core::Any);
+        }
+        cores
+    }
+
+    /// Groups the processors of this device under the debug port and access port they are
+    /// reached through.
+    pub fn debug_topology(&self) -> DebugTopology<'_> {
+        let mut ports: Vec<DebugPortNode> = Vec::new();
+        for processor in &self.processors {
+            let port = match ports.iter().position(|p| p.dp == processor.dp) {
+                Some(i) => &mut ports[i],
+                None => {
+                    ports.push(DebugPortNode {
+                        dp: processor.dp,
+                        access_ports: Vec::new(),
+                    });
+                    ports.last_mut().unwrap()
+                }
+            };
+            match port.access_ports.iter_mut().find(|a| a.ap == processor.ap) {
+                Some(ap) => ap.processors.push(processor),
+                None => port.access_ports.push(AccessPortNode {
+                    ap: processor.ap,
+                    processors: vec![processor],
+                }),
+            }
+        }
+        ports.sort_by_key(|p| p.dp);
+        for port in &mut ports {
+            port.access_ports.sort_by_key(|a| a.ap);
+        }
+        DebugTopology { ports }
+    }
+
+    /// The distinct flash algorithm files referenced by this device, in declaration order.
+    ///
+    /// Paths are compared after normalization, so `Flash/alg.FLM` and `./Flash//alg.FLM` are
+    /// considered the same file.
+    pub fn algorithm_files(&self) -> Vec<&Path> {
+        let mut seen = Vec::new();
+        let mut files = Vec::new();
+        for algorithm in &self.algorithms {
+            let normalized: Vec<Component> = algorithm
+                .file_name
+                .components()
+                .filter(|c| *c != Component::CurDir)
+                .collect();
+            if !seen.contains(&normalized) {
+                seen.push(normalized);
+                files.push(algorithm.file_name.as_path());
+            }
+        }
+        files
+    }
+
+    /// A one line description of this device, e.g.
+    /// `STM32F407VG: Cortex-M4, 1 MB flash, 192 KB RAM`. Cores are written as their `Dcore`.
+    pub fn summary(&self) -> String {
+        let cores = self
+            .processors
+            .iter()
+            .map(|p| p.core.to_string())
+            .collect::<Vec<_>>()
+            .join("+");
+        format!(
+            "{}: {}, {} flash, {} RAM",
+            self.name,
+            cores,
+            format_bytes(self.total_flash()),
+            format_bytes(self.total_ram())
+        )
+    }
+
+    /// This device as compact JSON, with object keys sorted so the output is the same on every
+    /// run.
+    pub fn to_json(&self) -> String {
+        self.to_json_value().to_string()
+    }
+
+    /// Like [`Device::to_json`], indented for reading.
+    pub fn to_json_pretty(&self) -> String {
+        // A `Value` always has string keys, so it always serializes.
+        serde_json::to_string_pretty(&self.to_json_value()).unwrap_or_default()
+    }
+
+    fn to_json_value(&self) -> serde_json::Value {
+        // Every field serializes to JSON: maps are keyed by strings, and the paths were read from
+        // a UTF-8 document.
+        serde_json::to_value(self).expect("devices always serialize to JSON")
+    }
 }
 
 impl DeviceBuilder {
-    fn from_elem(e: &Node) -> Self {
+    fn from_elem(e: &Node, opts: &ParseOptions) -> Self {
         let memories = Memories(HashMap::new());
         let mut family = None;
         let mut sub_family = None;
@@ -610,11 +2236,19 @@ impl DeviceBuilder {
             sub_family = e.attribute("DsubFamily").map(|f| f.to_string());
         }
 
+        // A variant is named by its own `Dvariant` alone; its device's `Dname` is only
+        // inherited as `base_device`.
+        let name = match e.tag_name().name() {
+            "variant" => e.attribute("Dvariant"),
+            _ => e.attribute("Dname").or_else(|| e.attribute("Dvariant")),
+        };
+        let (name, silicon_revision) = match name.map(split_revision) {
+            Some((name, revision)) => (Some(name), revision),
+            None => (None, None),
+        };
+
         DeviceBuilder {
-            name: e
-                .attribute("Dname")
-                .or_else(|| e.attribute("Dvariant"))
-                .map(|f| f.to_string()),
+            name,
             vendor: e.attribute("Dvendor").map(|f| f.to_string()),
             memories,
             algorithms: Vec::new(),
@@ -622,6 +2256,29 @@ impl DeviceBuilder {
             debugs: DebugsBuilder(Vec::new()),
             family,
             sub_family,
+            base_device: None,
+            condition: e.attribute("condition").map(|f| f.to_string()),
+            description: e
+                .children()
+                .find(|c| c.tag_name().name() == "description")
+                .and_then(|d| d.text())
+                .map(|text| text.split_whitespace().collect::<Vec<_>>().join(" "))
+                .filter(|text| !text.is_empty()),
+            extra_attributes: extra_attributes(
+                e,
+                &[
+                    "Dfamily",
+                    "DsubFamily",
+                    "Dname",
+                    "Dvariant",
+                    "Dvendor",
+                    "condition",
+                ],
+                opts,
+            ),
+            variants: Vec::new(),
+            silicon_revision,
+            removed_algorithms: Vec::new(),
         }
     }
 
@@ -640,19 +2297,54 @@ impl DeviceBuilder {
             None => return Err(format_err!("Device found without a processor {}", name)),
         };
 
+        // The device's own algorithms come before inherited ones, so keeping the first of each
+        // duplicate keeps the most specific, and the stable sort keeps them ahead at equal keys.
+        let mut algorithms: Vec<Algorithm> = Vec::with_capacity(self.algorithms.len());
+        for algorithm in self.algorithms {
+            let duplicate = algorithms.iter().any(|a| {
+                a.file_name == algorithm.file_name
+                    && a.start == algorithm.start
+                    && a.size == algorithm.size
+            });
+            if !duplicate {
+                algorithms.push(algorithm);
+            }
+        }
+        algorithms.sort_by_key(|a| (a.start, !a.default));
+
         Ok(Device {
             processors,
             name,
             memories: self.memories,
-            algorithms: self.algorithms,
+            algorithms,
             vendor: self.vendor,
             family,
             sub_family: self.sub_family,
+            base_device: self.base_device,
+            condition: self.condition,
+            description: self.description,
+            extra_attributes: self.extra_attributes,
+            pack_version: None,
+            variants: self.variants,
+            silicon_revision: self.silicon_revision,
         })
     }
 
     fn add_parent(mut self, parent: &Self) -> Result<Self, Error> {
-        self.algorithms.extend_from_slice(&parent.algorithms);
+        let removed = &self.removed_algorithms;
+        self.algorithms.extend(
+            parent
+                .algorithms
+                .iter()
+                .filter(|alg| !removed.contains(&alg.file_name))
+                .cloned(),
+        );
+        for removed in &parent.removed_algorithms {
+            if !self.removed_algorithms.contains(removed) {
+                self.removed_algorithms.push(removed.clone());
+            }
+        }
+        inherit_extra_attributes(&mut self.extra_attributes, &parent.extra_attributes);
         Ok(Self {
             name: self.name.or(parent.name.clone()),
             algorithms: self.algorithms,
@@ -665,6 +2357,13 @@ impl DeviceBuilder {
             vendor: self.vendor.or(parent.vendor.clone()),
             family: self.family.or(parent.family.clone()),
             sub_family: self.sub_family.or(parent.sub_family.clone()),
+            base_device: self.base_device,
+            condition: self.condition.or(parent.condition.clone()),
+            description: self.description.or(parent.description.clone()),
+            extra_attributes: self.extra_attributes,
+            variants: self.variants,
+            silicon_revision: self.silicon_revision.or(parent.silicon_revision.clone()),
+            removed_algorithms: self.removed_algorithms,
         })
     }
 
@@ -690,78 +2389,157 @@ impl DeviceBuilder {
         self.algorithms.push(alg);
         self
     }
+
+    /// Adds the `<algorithm>` element `e`.
+    ///
+    /// The pack format has no way to drop an inherited algorithm, say from a device lacking the
+    /// flash bank it programs, so we recognize `<algorithm name="..." remove="1"/>` as dropping
+    /// the algorithm of that `name` inherited from enclosing elements. Such an element needs no
+    /// other attributes and adds nothing.
+    fn add_algorithm_elem(
+        &mut self,
+        e: &Node,
+        opts: &ParseOptions,
+        diags: &mut dyn Diagnostics,
+    ) -> &mut Self {
+        let remove = attr_parse(e, "remove")
+            .map(|nb: NumberBool| nb.into())
+            .unwrap_or(false);
+        if !remove {
+            FromElem::from_elem_with(e, opts)
+                .ok_report(e, diags)
+                .map(|alg| self.add_algorithm(alg));
+            return self;
+        }
+        match e.attribute("name") {
+            Some(name) => self
+                .removed_algorithms
+                .push(PathBuf::from(opts.path_style.normalize(name))),
+            None => diags.report(Diagnostic::new(e, "Algorithm removal found without a name")),
+        }
+        self
+    }
+}
+
+/// Collapses variants whose names share a [`DeviceName`] base, differing only in package and
+/// temperature grade, into the first of them, renamed to the base and listing every collapsed
+/// name in `variants`. A variant that shares its base with no other is left as is.
+fn collapse_variants(variants: Vec<DeviceBuilder>) -> Vec<DeviceBuilder> {
+    let mut collapsed: Vec<(String, DeviceBuilder)> = Vec::with_capacity(variants.len());
+    for mut variant in variants {
+        let name = variant.name.clone().unwrap_or_default();
+        let base = DeviceName::parse(&name).base;
+        match collapsed.iter_mut().find(|(b, _)| *b == base) {
+            Some((_, first)) => first.variants.push(name),
+            None => {
+                variant.variants.push(name);
+                collapsed.push((base, variant));
+            }
+        }
+    }
+    collapsed
+        .into_iter()
+        .map(|(base, mut variant)| {
+            if variant.variants.len() > 1 {
+                variant.name = Some(base);
+            } else {
+                variant.variants.clear();
+            }
+            variant
+        })
+        .collect()
 }
 
-fn parse_device(e: &Node) -> Vec<DeviceBuilder> {
-    let mut device = DeviceBuilder::from_elem(e);
+fn parse_device(e: &Node, opts: &ParseOptions, diags: &mut dyn Diagnostics) -> Vec<DeviceBuilder> {
+    let mut device = DeviceBuilder::from_elem(e, opts);
+    let base_device = device.name.clone();
     let variants: Vec<DeviceBuilder> = e
         .children()
         .filter_map(|child| match child.tag_name().name() {
-            "variant" => Some(DeviceBuilder::from_elem(&child)),
+            "variant" => {
+                let mut variant = DeviceBuilder::from_elem(&child, opts);
+                if variant.name.is_none() {
+                    diags.report(Diagnostic::new(
+                        &child,
+                        format!(
+                            "Variant of {} found without a Dvariant",
+                            base_device.as_deref().unwrap_or("<unnamed>")
+                        ),
+                    ));
+                    return None;
+                }
+                variant.base_device = base_device.clone();
+                Some(variant)
+            }
             "memory" => {
-                FromElem::from_elem(&child)
-                    .ok_warn()
+                FromElem::from_elem_with(&child, opts)
+                    .ok_report(&child, diags)
                     .map(|mem| device.add_memory(mem));
                 None
             }
             "algorithm" => {
-                FromElem::from_elem(&child)
-                    .ok_warn()
-                    .map(|alg| device.add_algorithm(alg));
+                device.add_algorithm_elem(&child, opts, diags);
                 None
             }
             "processor" => {
-                FromElem::from_elem(&child)
-                    .ok_warn()
+                FromElem::from_elem_with(&child, opts)
+                    .ok_report(&child, diags)
                     .map(|prc| device.add_processor(prc));
                 None
             }
             "debug" => {
-                DebugsBuilder::from_elem_and_parent(&child, e)
-                    .ok_warn()
+                DebugsBuilder::from_elem_and_parent(&child, e, opts, diags)
+                    .ok_report(&child, diags)
                     .map(|debug| device.add_debug(debug));
                 None
             }
             _ => None,
         })
         .collect::<Vec<_>>();
+    let variants = if opts.collapse_variants {
+        collapse_variants(variants)
+    } else {
+        variants
+    };
     if variants.is_empty() {
         vec![device]
     } else {
         variants
             .into_iter()
-            .flat_map(|bld| bld.add_parent(&device).ok_warn())
+            .flat_map(|bld| bld.add_parent(&device).ok_report(e, diags))
             .collect()
     }
 }
 
-fn parse_sub_family(e: &Node) -> Vec<DeviceBuilder> {
-    let mut sub_family_device = DeviceBuilder::from_elem(e);
+fn parse_sub_family(
+    e: &Node,
+    opts: &ParseOptions,
+    diags: &mut dyn Diagnostics,
+) -> Vec<DeviceBuilder> {
+    let mut sub_family_device = DeviceBuilder::from_elem(e, opts);
     let mut devices: Vec<DeviceBuilder> = Vec::new();
 
     for child in e.children() {
         match child.tag_name().name() {
             "device" => {
-                devices.extend(parse_device(&child));
+                devices.extend(parse_device(&child, opts, diags));
             }
             "memory" => {
-                FromElem::from_elem(&child)
-                    .ok_warn()
+                FromElem::from_elem_with(&child, opts)
+                    .ok_report(&child, diags)
                     .map(|mem| sub_family_device.add_memory(mem));
             }
             "algorithm" => {
-                FromElem::from_elem(&child)
-                    .ok_warn()
-                    .map(|alg| sub_family_device.add_algorithm(alg));
+                sub_family_device.add_algorithm_elem(&child, opts, diags);
             }
             "processor" => {
-                FromElem::from_elem(&child)
-                    .ok_warn()
+                FromElem::from_elem_with(&child, opts)
+                    .ok_report(&child, diags)
                     .map(|prc| sub_family_device.add_processor(prc));
             }
             "debug" => {
-                DebugsBuilder::from_elem_and_parent(&child, e)
-                    .ok_warn()
+                DebugsBuilder::from_elem_and_parent(&child, e, opts, diags)
+                    .ok_report(&child, diags)
                     .map(|debug| sub_family_device.add_debug(debug));
             }
             _ => continue,
@@ -769,38 +2547,40 @@ fn parse_sub_family(e: &Node) -> Vec<DeviceBuilder> {
     }
     devices
         .into_iter()
-        .flat_map(|bldr| bldr.add_parent(&sub_family_device).ok_warn())
+        .flat_map(|bldr| bldr.add_parent(&sub_family_device).ok_report(e, diags))
         .collect()
 }
 
-fn parse_family(e: &Node) -> Result<Vec<Device>, Error> {
-    let mut family_device = DeviceBuilder::from_elem(e);
+fn parse_family(
+    e: &Node,
+    opts: &ParseOptions,
+    diags: &mut dyn Diagnostics,
+) -> Result<Vec<Device>, Error> {
+    let mut family_device = DeviceBuilder::from_elem(e, opts);
     let all_devices: Vec<DeviceBuilder> = e
         .children()
         .flat_map(|child| match child.tag_name().name() {
-            "subFamily" => parse_sub_family(&child),
-            "device" => parse_device(&child),
+            "subFamily" => parse_sub_family(&child, opts, diags),
+            "device" => parse_device(&child, opts, diags),
             "memory" => {
-                FromElem::from_elem(&child)
-                    .ok_warn()
+                FromElem::from_elem_with(&child, opts)
+                    .ok_report(&child, diags)
                     .map(|mem| family_device.add_memory(mem));
                 Vec::new()
             }
             "algorithm" => {
-                FromElem::from_elem(&child)
-                    .ok_warn()
-                    .map(|alg| family_device.add_algorithm(alg));
+                family_device.add_algorithm_elem(&child, opts, diags);
                 Vec::new()
             }
             "processor" => {
-                FromElem::from_elem(&child)
-                    .ok_warn()
+                FromElem::from_elem_with(&child, opts)
+                    .ok_report(&child, diags)
                     .map(|prc| family_device.add_processor(prc));
                 Vec::new()
             }
             "debug" => {
-                DebugsBuilder::from_elem_and_parent(&child, e)
-                    .ok_warn()
+                DebugsBuilder::from_elem_and_parent(&child, e, opts, diags)
+                    .ok_report(&child, diags)
                     .map(|debug| family_device.add_debug(debug));
                 Vec::new()
             }
@@ -816,14 +2596,2854 @@ fn parse_family(e: &Node) -> Result<Vec<Device>, Error> {
 #[derive(Default, Serialize)]
 pub struct Devices(pub HashMap<String, Device>);
 
+/// Summary counts over a set of [`Devices`], see [`Devices::stats`].
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct IndexStats {
+    pub device_count: usize,
+    pub vendor_count: usize,
+    /// The number of devices with at least one processor of each core.
+    pub core_histogram: HashMap<Core, usize>,
+    pub total_flash_bytes: u64,
+    pub total_ram_bytes: u64,
+}
+
+/// The name, family, vendor and cores of a device, as listed by [`Devices::list_names`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DeviceMeta {
+    pub name: String,
+    pub family: String,
+    pub vendor: Option<String>,
+    /// The distinct cores of the device's processors, in declaration order, as given by
+    /// [`Device::cores`].
+    pub cores: Vec<Core>,
+}
+
+/// What a `family`, `subFamily`, `device` or `variant` element passes on to the elements inside
+/// it when listing devices.
+#[derive(Clone, Default)]
+struct MetaScope {
+    family: Option<String>,
+    vendor: Option<String>,
+    processors: Vec<(Option<String>, Core)>,
+}
+
+impl MetaScope {
+    fn enter(&self, e: &Node) -> Self {
+        let mut scope = self.clone();
+        if e.tag_name().name() == "family" {
+            scope.family = e.attribute("Dfamily").map(String::from);
+        }
+        if let Some(vendor) = e.attribute("Dvendor") {
+            scope.vendor = Some(vendor.to_string());
+        }
+        // Like a full parse, processors declared here come before the inherited ones they don't
+        // override.
+        let mut processors: Vec<(Option<String>, Core)> = Vec::new();
+        for processor in e.children().filter(|c| c.tag_name().name() == "processor") {
+            let Some((core, _)) = processor
+                .attribute("Dcore")
+                .and_then(|c| parse_core(c).ok())
+            else {
+                continue;
+            };
+            let name = processor.attribute("Pname").map(String::from);
+            if !processors.iter().any(|(n, _)| *n == name) {
+                processors.push((name, core));
+            }
+        }
+        for (name, core) in &self.processors {
+            if !processors.iter().any(|(n, _)| n == name) {
+                processors.push((name.clone(), core.clone()));
+            }
+        }
+        scope.processors = processors;
+        scope
+    }
+
+    fn list(&self, e: &Node, out: &mut Vec<DeviceMeta>) {
+        let scope = self.enter(e);
+        let kind = e.tag_name().name();
+        let children: Vec<Node> = e
+            .children()
+            .filter(|c| {
+                matches!(
+                    (kind, c.tag_name().name()),
+                    ("family", "subFamily" | "device")
+                        | ("subFamily", "device")
+                        | ("device", "variant")
+                )
+            })
+            .collect();
+        if !children.is_empty() {
+            for child in children {
+                scope.list(&child, out);
+            }
+            return;
+        }
+        let name = match kind {
+            "variant" => e.attribute("Dvariant"),
+            "device" => e.attribute("Dname").or_else(|| e.attribute("Dvariant")),
+            _ => None,
+        };
+        let (Some(name), Some(family)) = (name, scope.family) else {
+            return;
+        };
+        let mut cores: Vec<Core> = Vec::new();
+        for (_, core) in scope.processors {
+            if !cores.contains(&core) {
+                cores.push(core);
+            }
+        }
+        if cores.len() > 1 {
+            cores.retain(|core| *core != Core::Any);
+        }
+        if cores.is_empty() {
+            log::warn!("Device found without a processor {}", name);
+            return;
+        }
+        out.push(DeviceMeta {
+            name: split_revision(name).0,
+            family,
+            vendor: scope.vendor,
+            cores,
+        });
+    }
+}
+
+impl Devices {
+    /// Lists the devices in `xml`, either a whole PDSC or its `<devices>` element, without
+    /// building their memories, algorithms and debug configuration.
+    ///
+    /// Devices come in declaration order and are named as [`Devices::from_string`] would name
+    /// them; a name declared twice is listed once. This is much cheaper than a full parse when
+    /// all that's wanted is a catalog of what a pack contains.
+    pub fn list_names(xml: &str) -> Result<Vec<DeviceMeta>, Error> {
+        let xml = xml.strip_prefix('\u{feff}').unwrap_or(xml);
+        check_nesting(xml)?;
+        let doc = roxmltree::Document::parse(xml)?;
+        let root = doc.root_element();
+        let devices = if root.tag_name().name() == "devices" {
+            Some(root)
+        } else {
+            root.children().find(|c| c.tag_name().name() == "devices")
+        };
+        let mut listed = Vec::new();
+        for family in devices.iter().flat_map(|d| d.children()) {
+            if family.tag_name().name() == "family" {
+                MetaScope::default().list(&family, &mut listed);
+            }
+        }
+        let mut seen = BTreeSet::new();
+        listed.retain(|meta| seen.insert(meta.name.clone()));
+        Ok(listed)
+    }
+
+    /// Renames the device `old` to `new`, keeping its map key and `Device::name` consistent.
+    ///
+    /// Returns false, leaving the devices untouched, if there is no device named `old` or a
+    /// different device is already named `new`.
+    pub fn rename(&mut self, old: &str, new: String) -> bool {
+        if old == new {
+            return self.0.contains_key(old);
+        }
+        if self.0.contains_key(&new) {
+            return false;
+        }
+        match self.0.remove(old) {
+            Some(mut device) => {
+                device.name = new.clone();
+                self.0.insert(new, device);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Adds the devices of the pack `other`, released as `other_version`.
+    ///
+    /// A device present in both is replaced only if `other_version` is newer than the
+    /// [`Device::pack_version`] of the device already here; a device without a known version is
+    /// always replaced.
+    pub fn merge_preferring(&mut self, other: Devices, other_version: &str) {
+        for (name, mut device) in other.0 {
+            device.pack_version = Some(other_version.to_string());
+            match self.0.entry(name) {
+                Entry::Occupied(mut entry) => {
+                    let newer = entry.get().pack_version.as_deref().map_or(true, |version| {
+                        compare_versions(other_version, version) == Ordering::Greater
+                    });
+                    if newer {
+                        entry.insert(device);
+                    }
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(device);
+                }
+            }
+        }
+    }
+
+    /// A one call overview of these devices.
+    pub fn stats(&self) -> IndexStats {
+        let mut core_histogram = HashMap::new();
+        for device in self.0.values() {
+            for core in device.cores() {
+                *core_histogram.entry(core).or_insert(0) += 1;
+            }
+        }
+        IndexStats {
+            device_count: self.0.len(),
+            vendor_count: self.vendors().len(),
+            core_histogram,
+            total_flash_bytes: self
+                .0
+                .values()
+                .map(Device::total_flash)
+                .fold(0, u64::saturating_add),
+            total_ram_bytes: self
+                .0
+                .values()
+                .map(Device::total_ram)
+                .fold(0, u64::saturating_add),
+        }
+    }
+
+    /// The distinct vendor names of these devices, as given by [`Device::vendor_name`].
+    pub fn vendors(&self) -> BTreeSet<String> {
+        self.0
+            .values()
+            .filter_map(Device::vendor_name)
+            .map(String::from)
+            .collect()
+    }
+
+    /// Keeps only the devices for which `keep` returns true.
+    pub fn retain<F>(&mut self, keep: F)
+    where
+        F: FnMut(&String, &mut Device) -> bool,
+    {
+        self.0.retain(keep);
+    }
+
+    /// Keeps only the devices of `vendor`, compared by [`Device::vendor_name`]. A `:id` suffix
+    /// on `vendor` is ignored, so both `STMicroelectronics` and `STMicroelectronics:13` work.
+    pub fn retain_vendor(&mut self, vendor: &str) {
+        let vendor = strip_vendor_id(vendor);
+        self.retain(|_, device| device.vendor_name() == Some(vendor));
+    }
+
+    /// Keeps only the devices with at least one processor of `core`, as given by
+    /// [`Device::cores`].
+    pub fn retain_core(&mut self, core: &Core) {
+        self.retain(|_, device| device.cores().contains(core));
+    }
+
+    /// Every processor of every device, alongside the name of its device, in no particular
+    /// order.
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    ///
+    /// use cmsis_pack::pdsc::Devices;
+    /// use cmsis_pack::utils::FromElem;
+    ///
+    /// let devices = Devices::from_string(
+    ///     r#"<devices>
+    ///       <family Dfamily="STM32H7" Dvendor="STMicroelectronics:13">
+    ///         <device Dname="STM32H745ZI">
+    ///           <processor Pname="CM7" Dcore="Cortex-M7"/>
+    ///           <processor Pname="CM4" Dcore="Cortex-M4"/>
+    ///         </device>
+    ///         <device Dname="STM32H743ZI">
+    ///           <processor Dcore="Cortex-M7"/>
+    ///         </device>
+    ///       </family>
+    ///     </devices>"#,
+    /// )
+    /// .unwrap();
+    ///
+    /// let mut processors: HashMap<&str, usize> = HashMap::new();
+    /// for (device, _) in devices.processors() {
+    ///     *processors.entry(device).or_default() += 1;
+    /// }
+    /// let multi_core = processors.values().filter(|&&count| count > 1).count();
+    /// assert_eq!(multi_core, 1);
+    /// ```
+    pub fn processors(&self) -> impl Iterator<Item = (&str, &Processor)> {
+        self.0.values().flat_map(|device| {
+            device
+                .processors
+                .iter()
+                .map(move |processor| (device.name.as_str(), processor))
+        })
+    }
+
+    /// Every algorithm of every device, alongside the name of its device, in no particular
+    /// order.
+    pub fn algorithms(&self) -> impl Iterator<Item = (&str, &Algorithm)> {
+        self.0.values().flat_map(|device| {
+            device
+                .algorithms
+                .iter()
+                .map(move |algorithm| (device.name.as_str(), algorithm))
+        })
+    }
+
+    /// Writes the devices as JSON Lines: one compact JSON object per device, sorted by name.
+    ///
+    /// Objects are written with sorted keys, so the output is the same on every run.
+    pub fn write_jsonl(&self, w: &mut impl Write) -> Result<(), Error> {
+        let mut devices: Vec<&Device> = self.0.values().collect();
+        devices.sort_by(|l, r| l.name.cmp(&r.name));
+        for device in devices {
+            serde_json::to_writer(&mut *w, &serde_json::to_value(device)?)?;
+            w.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+}
+
 impl FromElem for Devices {
     fn from_elem(e: &Node) -> Result<Self, Error> {
-        e.children()
-            .try_fold(HashMap::new(), |mut res, c| {
-                let add_this = parse_family(&c)?;
-                res.extend(add_this.into_iter().map(|dev| (dev.name.clone(), dev)));
-                Ok(res)
+        Self::from_elem_with(e, &ParseOptions::default())
+    }
+
+    fn from_elem_with(e: &Node, opts: &ParseOptions) -> Result<Self, Error> {
+        Self::from_elem_with_diagnostics(e, opts, &mut LogDiagnostics)
+    }
+
+    fn from_elem_with_diagnostics(
+        e: &Node,
+        opts: &ParseOptions,
+        diags: &mut dyn Diagnostics,
+    ) -> Result<Self, Error> {
+        // Each device with the family it came from and how many devices of the same name that
+        // family declared before it, to find its element should a later declaration replace it.
+        let mut devices: HashMap<String, (Device, Node, usize)> = HashMap::new();
+        for c in e.children() {
+            let mut seen: HashMap<String, usize> = HashMap::new();
+            for dev in parse_family(&c, opts, diags)? {
+                let nth = seen.entry(dev.name.clone()).or_default();
+                let replaced = devices.insert(dev.name.clone(), (dev, c, *nth));
+                *nth += 1;
+                // The last declaration wins, as it always has; report the one it replaces.
+                if let Some((dropped, family, nth)) = replaced {
+                    // Variants are named by `Dvariant`, devices by `Dname`.
+                    let decl = family
+                        .descendants()
+                        .filter(|d| {
+                            d.attribute("Dvariant").or_else(|| d.attribute("Dname"))
+                                == Some(dropped.name.as_str())
+                        })
+                        .nth(nth)
+                        .unwrap_or(family);
+                    diags.report(Diagnostic::new(
+                        &decl,
+                        format!(
+                            "Duplicate device {}, replaced by a later declaration",
+                            dropped.name
+                        ),
+                    ));
+                }
+            }
+        }
+        Ok(Devices(
+            devices
+                .into_iter()
+                .map(|(name, (dev, _, _))| (name, dev))
+                .collect(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::PathStyle;
+
+    fn processor(name: Option<&str>, unit: usize) -> Processor {
+        Processor {
+            core: Core::CortexM4,
+            core_revision: None,
+            fpu: FPU::None,
+            mpu: MPU::NotPresent,
+            mpu_regions: None,
+            tz: TrustZone::NotPresent,
+            endian: Endian::Little,
+            ap: AccessPort::default(),
+            dp: 0,
+            address: None,
+            svd: None,
+            name: name.map(String::from),
+            unit,
+            default_reset_sequence: None,
+            targetsel: None,
+            extra_attributes: HashMap::new(),
+            is_boot_core: false,
+        }
+    }
+
+    fn debug(name: Option<&str>, unit: Option<usize>) -> Debug {
+        Debug {
+            dp: None,
+            ap: None,
+            address: None,
+            svd: None,
+            name: name.map(String::from),
+            unit,
+            default_reset_sequence: None,
+            targetsel: None,
+        }
+    }
+
+    #[test]
+    fn newer_application_cores() {
+        for (name, arch) in [
+            ("Cortex-A53", Architecture::Armv8A),
+            ("Cortex-A55", Architecture::Armv82A),
+            ("Cortex-A65", Architecture::Armv82A),
+            ("Cortex-A75", Architecture::Armv82A),
+            ("Cortex-A76", Architecture::Armv82A),
+            ("Cortex-A77", Architecture::Armv82A),
+            ("Cortex-A78", Architecture::Armv82A),
+        ] {
+            let core: Core = name.parse().unwrap();
+            assert_eq!(core.architecture(), arch, "{}", name);
+        }
+    }
+
+    #[test]
+    fn core_architecture() {
+        assert_eq!(Core::CortexM0Plus.architecture(), Architecture::Armv6M);
+        assert_eq!(Core::SC300.architecture(), Architecture::Armv7M);
+        assert_eq!(Core::CortexM7.architecture(), Architecture::Armv7EM);
+        assert_eq!(Core::ARMV8MBL.architecture(), Architecture::Armv8MBaseline);
+        assert_eq!(Core::StarMC1.architecture(), Architecture::Armv8MMainline);
+        assert_eq!(
+            Core::CortexM85.architecture(),
+            Architecture::Armv81MMainline
+        );
+        assert_eq!(Core::CortexR5.architecture(), Architecture::Armv7R);
+        assert_eq!(Core::CortexA9.architecture(), Architecture::Armv7A);
+        assert_eq!(Core::Any.architecture(), Architecture::Any);
+    }
+
+    #[test]
+    fn wildcard_core() {
+        assert_eq!("*".parse::<Core>().unwrap(), Core::Any);
+        assert!(Core::Any.matches(&Core::CortexM4));
+        assert!(Core::CortexM4.matches(&Core::Any));
+        assert!(Core::CortexM4.matches(&Core::CortexM4));
+        assert!(!Core::CortexM4.matches(&Core::CortexM7));
+        assert!(!Core::Any.is_cortex_m() && !Core::Any.is_cortex_r() && !Core::Any.is_cortex_a());
+
+        let mut processor = Processor::new(Core::Any);
+        processor.fpu = FPU::DoublePrecision;
+        assert_eq!(processor.fpu_gcc_flag(), None);
+
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="Family">
+                <processor Dcore="*"/>
+                <device Dname="Generic"/>
+                <device Dname="Specific">
+                  <processor Pname="cm4" Dcore="Cortex-M4"/>
+                </device>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        assert_eq!(devices.0["Generic"].cores(), vec![Core::Any]);
+        assert_eq!(
+            devices.0["Generic"].summary(),
+            "Generic: *, 0 B flash, 0 B RAM"
+        );
+        let specific = &devices.0["Specific"];
+        assert_eq!(specific.processors.len(), 2);
+        assert_eq!(specific.cores(), vec![Core::CortexM4]);
+    }
+
+    #[test]
+    fn core_profile_predicates() {
+        use Core::*;
+        let classes = [
+            (Any, ""),
+            (CortexM0, "M"),
+            (CortexM0Plus, "M"),
+            (CortexM1, "M"),
+            (CortexM3, "M"),
+            (CortexM4, "M"),
+            (CortexM7, "M"),
+            (CortexM23, "M"),
+            (CortexM33, "M"),
+            (CortexM35P, "M"),
+            (CortexM55, "M"),
+            (CortexM85, "M"),
+            (StarMC1, "M"),
+            (SC000, "M"),
+            (SC300, "M"),
+            (ARMV8MBL, "M"),
+            (ARMV8MML, "M"),
+            (ARMV81MML, "M"),
+            (CortexR4, "R"),
+            (CortexR5, "R"),
+            (CortexR7, "R"),
+            (CortexR8, "R"),
+            (CortexA5, "A"),
+            (CortexA7, "A"),
+            (CortexA8, "A"),
+            (CortexA9, "A"),
+            (CortexA15, "A"),
+            (CortexA17, "A"),
+            (CortexA32, "A"),
+            (CortexA35, "A"),
+            (CortexA53, "A"),
+            (CortexA57, "A"),
+            (CortexA72, "A"),
+            (CortexA73, "A"),
+            (CortexA55, "A"),
+            (CortexA65, "A"),
+            (CortexA75, "A"),
+            (CortexA76, "A"),
+            (CortexA77, "A"),
+            (CortexA78, "A"),
+        ];
+        for (core, class) in classes {
+            assert_eq!(core.is_cortex_m(), class == "M", "{:?}", core);
+            assert_eq!(core.is_cortex_r(), class == "R", "{:?}", core);
+            assert_eq!(core.is_cortex_a(), class == "A", "{:?}", core);
+        }
+    }
+
+    #[test]
+    fn core_string_round_trip() {
+        for core in Core::ALL {
+            let dcore = core.to_string();
+            assert_eq!(dcore.parse::<Core>().unwrap(), core);
+            let json = serde_json::to_string(&core).unwrap();
+            assert_eq!(json, format!("\"{}\"", dcore));
+            assert_eq!(serde_json::from_str::<Core>(&json).unwrap(), core);
+            let old = format!("\"{:?}\"", core);
+            assert_eq!(serde_json::from_str::<Core>(&old).unwrap(), core);
+        }
+        assert_eq!(Core::CortexM0Plus.to_string(), "Cortex-M0+");
+        assert_eq!(Core::StarMC1.to_string(), "Star-MC1");
+        assert_eq!(Core::Any.to_string(), "*");
+        assert_eq!("*".parse::<Core>().unwrap(), Core::Any);
+        assert!(serde_json::from_str::<Core>("\"Cortex-M99\"").is_err());
+    }
+
+    #[test]
+    fn core_with_fpu_suffix() {
+        assert!(matches!(
+            parse_core("Cortex-M4F").unwrap(),
+            (Core::CortexM4, Some(FPU::SinglePrecision))
+        ));
+        assert!(matches!(
+            parse_core("Cortex-M7F").unwrap(),
+            (Core::CortexM7, Some(FPU::SinglePrecision))
+        ));
+        assert!(matches!(
+            parse_core("Cortex-M0+").unwrap(),
+            (Core::CortexM0Plus, None)
+        ));
+    }
+
+    #[test]
+    fn core_with_revision_suffix() {
+        assert!(matches!(
+            parse_core("Cortex-M33+").unwrap(),
+            (Core::CortexM33, None)
+        ));
+        assert!(matches!(
+            parse_core("Cortex-M4 r0p1").unwrap(),
+            (Core::CortexM4, None)
+        ));
+        assert!(matches!(
+            parse_core("Cortex-M4F r0p1").unwrap(),
+            (Core::CortexM4, Some(FPU::SinglePrecision))
+        ));
+        assert!(parse_core("Cortex-X1").is_err());
+        assert!(parse_core("Cortex-M4 rev").is_err());
+    }
+
+    #[test]
+    fn core_case_and_whitespace() {
+        for name in ["cortex-m4", " Cortex-M4 ", "CORTEX-M4\t", "\n cortex-M4"] {
+            assert_eq!(name.parse::<Core>().unwrap(), Core::CortexM4, "{:?}", name);
+        }
+        assert_eq!("star-mc1".parse::<Core>().unwrap(), Core::StarMC1);
+        assert_eq!("armv8mml".parse::<Core>().unwrap(), Core::ARMV8MML);
+        assert!(matches!(
+            parse_core(" cortex-m4f ").unwrap(),
+            (Core::CortexM4, Some(FPU::SinglePrecision))
+        ));
+
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="Family">
+                <processor Dcore=" cortex-m7 "/>
+                <device Dname="Device"/>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        let core = &devices.0["Device"].processors[0].core;
+        assert_eq!(core, &Core::CortexM7);
+        assert_eq!(serde_json::to_value(core).unwrap(), "Cortex-M7");
+    }
+
+    #[test]
+    fn core_fpu_suffix_does_not_override_dfpu() {
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="Family">
+                <device Dname="Implied">
+                  <processor Dcore="Cortex-M7F"/>
+                </device>
+                <device Dname="Explicit">
+                  <processor Dcore="Cortex-M7F" Dfpu="DP_FPU"/>
+                </device>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        let implied = &devices.0["Implied"].processors[0];
+        assert!(matches!(implied.core, Core::CortexM7));
+        assert!(matches!(implied.fpu, FPU::SinglePrecision));
+        let explicit = &devices.0["Explicit"].processors[0];
+        assert!(matches!(explicit.fpu, FPU::DoublePrecision));
+    }
+
+    #[test]
+    fn core_revision_inherited_from_family() {
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="Family">
+                <processor Dcore="Cortex-M7" Dcorever="r0p1"/>
+                <device Dname="Inherited"/>
+                <device Dname="Overridden">
+                  <processor Dcorever="r1p2"/>
+                </device>
+              </family>
+              <family Dfamily="Other">
+                <device Dname="Undeclared">
+                  <processor Dcore="Cortex-M7"/>
+                </device>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        let revision = |name: &str| devices.0[name].processors[0].core_revision.clone();
+        assert_eq!(revision("Inherited").as_deref(), Some("r0p1"));
+        assert_eq!(revision("Overridden").as_deref(), Some("r1p2"));
+        assert_eq!(revision("Undeclared"), None);
+    }
+
+    #[test]
+    fn mpu_regions_inherited_unless_overridden() {
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="Family">
+                <processor Dcore="Cortex-M4" Dmpu="MPU" DnumMpuRegions="8"/>
+                <device Dname="Inherited"/>
+                <device Dname="Overridden">
+                  <processor DnumMpuRegions="16"/>
+                </device>
+              </family>
+              <family Dfamily="Other">
+                <device Dname="Undeclared">
+                  <processor Dcore="Cortex-M4" Dmpu="MPU"/>
+                </device>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        assert_eq!(devices.0["Inherited"].processors[0].mpu_regions, Some(8));
+        assert_eq!(devices.0["Overridden"].processors[0].mpu_regions, Some(16));
+        assert_eq!(devices.0["Undeclared"].processors[0].mpu_regions, None);
+    }
+
+    #[test]
+    fn algorithm_files_are_deduplicated() {
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="Family">
+                <processor Dcore="Cortex-M4"/>
+                <algorithm name="Flash\Main.FLM" start="0x08000000" size="0x80000"/>
+                <algorithm name="Flash/Main.FLM" start="0x08080000" size="0x80000"/>
+                <device Dname="Device">
+                  <algorithm name="./Flash/Main.FLM" start="0x08000000" size="0x100000"/>
+                  <algorithm name="Flash/OTP.FLM" start="0x1FFF7800" size="0x200"/>
+                </device>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        let files = devices.0["Device"].algorithm_files();
+        assert_eq!(
+            files,
+            vec![Path::new("./Flash/Main.FLM"), Path::new("Flash/OTP.FLM")]
+        );
+    }
+
+    #[test]
+    fn fpu_capabilities() {
+        assert!(!FPU::None.is_present());
+        assert!(FPU::SinglePrecision.is_present());
+        assert!(!FPU::SinglePrecision.is_double_precision());
+        assert!(FPU::DoublePrecision.is_double_precision());
+        assert_eq!(FPU::None.precision_bits(), None);
+        assert_eq!(FPU::SinglePrecision.precision_bits(), Some(32));
+        assert_eq!(FPU::DoublePrecision.precision_bits(), Some(64));
+    }
+
+    #[test]
+    fn gcc_fpu_flags() {
+        let flag = |core: Core, fpu: FPU| {
+            let mut processor = Processor::new(core);
+            processor.fpu = fpu;
+            processor.fpu_gcc_flag()
+        };
+        assert_eq!(flag(Core::CortexM4, FPU::None), None);
+        assert_eq!(
+            flag(Core::CortexM4, FPU::SinglePrecision),
+            Some("fpv4-sp-d16")
+        );
+        assert_eq!(
+            flag(Core::CortexM4, FPU::DoublePrecision),
+            Some("fpv4-sp-d16")
+        );
+        assert_eq!(
+            flag(Core::CortexM7, FPU::SinglePrecision),
+            Some("fpv5-sp-d16")
+        );
+        assert_eq!(flag(Core::CortexM7, FPU::DoublePrecision), Some("fpv5-d16"));
+        assert_eq!(
+            flag(Core::CortexM33, FPU::SinglePrecision),
+            Some("fpv5-sp-d16")
+        );
+        assert_eq!(
+            flag(Core::CortexM33, FPU::DoublePrecision),
+            Some("fpv5-sp-d16")
+        );
+        assert_eq!(
+            flag(Core::CortexM55, FPU::DoublePrecision),
+            Some("fpv5-d16")
+        );
+        assert_eq!(
+            flag(Core::CortexR5, FPU::DoublePrecision),
+            Some("vfpv3-d16")
+        );
+        assert_eq!(flag(Core::CortexM0, FPU::SinglePrecision), None);
+        assert_eq!(flag(Core::CortexA9, FPU::DoublePrecision), None);
+    }
+
+    #[test]
+    fn variants_record_base_device() {
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="STM32F4">
+                <processor Dcore="Cortex-M4"/>
+                <device Dname="STM32F407">
+                  <variant Dvariant="STM32F407VG"/>
+                  <variant Dvariant="STM32F407ZG"/>
+                </device>
+                <device Dname="STM32F401CC"/>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        assert_eq!(devices.0.len(), 3);
+        for variant in ["STM32F407VG", "STM32F407ZG"] {
+            assert_eq!(devices.0[variant].base_device.as_deref(), Some("STM32F407"));
+        }
+        assert_eq!(devices.0["STM32F401CC"].base_device, None);
+    }
+
+    #[test]
+    fn silicon_revision_split_from_name() {
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="STM32F4">
+                <processor Dcore="Cortex-M4"/>
+                <device Dname="STM32F407@B">
+                  <variant Dvariant="STM32F407VG"/>
+                </device>
+                <device Dname="STM32F405@Z"/>
+                <device Dname="STM32F401CC"/>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        let revised = &devices.0["STM32F405"];
+        assert_eq!(revised.name, "STM32F405");
+        assert_eq!(revised.silicon_revision.as_deref(), Some("Z"));
+        let variant = &devices.0["STM32F407VG"];
+        assert_eq!(variant.base_device.as_deref(), Some("STM32F407"));
+        assert_eq!(variant.silicon_revision.as_deref(), Some("B"));
+        let plain = &devices.0["STM32F401CC"];
+        assert_eq!(plain.name, "STM32F401CC");
+        assert_eq!(plain.silicon_revision, None);
+        assert_eq!(split_revision("@B"), ("@B".to_string(), None));
+    }
+
+    #[test]
+    fn variant_named_by_dvariant() {
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="Family">
+                <processor Dcore="Cortex-M4"/>
+                <device Dname="X">
+                  <variant Dvariant="X-Q"/>
+                  <variant/>
+                </device>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        let variant = &devices.0["X-Q"];
+        assert_eq!(variant.name, "X-Q");
+        assert_eq!(variant.base_device.as_deref(), Some("X"));
+        // The base device itself is only emitted through its variants.
+        assert_eq!(devices.0.len(), 1);
+    }
+
+    #[test]
+    fn debug_address_above_32_bits() {
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="Family">
+                <processor Pname="a53" Dcore="Cortex-A53"/>
+                <processor Pname="m4" Dcore="Cortex-M4"/>
+                <debug Pname="a53" address="0x1_0081_0000"/>
+                <debug Pname="m4" address="0xE0042000"/>
+                <device Dname="Device"/>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        let address = |name: &str| {
+            devices.0["Device"]
+                .processors
+                .iter()
+                .find(|p| p.name.as_deref() == Some(name))
+                .unwrap()
+                .address
+        };
+        assert_eq!(address("a53"), None);
+        assert_eq!(address("m4"), Some(0xE004_2000));
+
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="Family">
+                <processor Dcore="Cortex-A53"/>
+                <debug address="0x100810000"/>
+                <device Dname="Device"/>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        assert_eq!(
+            devices.0["Device"].processors[0].address,
+            Some(0x1_0081_0000)
+        );
+    }
+
+    #[test]
+    fn debug_topology_groups_processors() {
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="Family">
+                <processor Pname="cm33" Dcore="Cortex-M33"/>
+                <processor Pname="cm0" Dcore="Cortex-M0+" Punits="2"/>
+                <processor Pname="a53" Dcore="Cortex-A53"/>
+                <debug Pname="cm33" __dp="0" __ap="1"/>
+                <debug Pname="cm0" __dp="0" __ap="0"/>
+                <debug Pname="a53" __dp="1" __ap="0"/>
+                <device Dname="Device"/>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        let topology = devices.0["Device"].debug_topology();
+        let mut flattened = Vec::new();
+        for port in &topology.ports {
+            for ap in &port.access_ports {
+                for p in &ap.processors {
+                    flattened.push((port.dp, ap.ap.as_u64(), p.name.as_deref().unwrap(), p.unit));
+                }
+            }
+        }
+        assert_eq!(
+            flattened,
+            [
+                (0, 0, "cm0", 0),
+                (0, 0, "cm0", 1),
+                (0, 1, "cm33", 0),
+                (1, 0, "a53", 0),
+            ]
+        );
+        assert_eq!(topology.ports[0].access_ports.len(), 2);
+    }
+
+    #[test]
+    fn trustzone_inherited_from_family() {
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="Family">
+                <processor Dcore="Cortex-M33" Dfpu="SP_FPU" Dtz="TZ"/>
+                <device Dname="Device">
+                  <processor Dmpu="MPU"/>
+                  <variant Dvariant="Variant"/>
+                </device>
+                <device Dname="NoTz">
+                  <processor Dtz="NO_TZ"/>
+                </device>
+              </family>
+              <family Dfamily="Older">
+                <processor Dcore="Cortex-M4"/>
+                <device Dname="M4"/>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        let variant = &devices.0["Variant"].processors[0];
+        assert_eq!(variant.tz, TrustZone::Present);
+        assert_eq!(variant.mpu, MPU::Present);
+        assert_eq!(devices.0["NoTz"].processors[0].tz, TrustZone::NotPresent);
+        assert_eq!(devices.0["M4"].processors[0].tz, TrustZone::NotPresent);
+    }
+
+    #[test]
+    fn configurable_endian() {
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="RZ/T1" Dvendor="Renesas:117">
+                <processor Dcore="Cortex-R4" Dfpu="DP_FPU" Dendian="Configurable"/>
+                <device Dname="R7S910018">
+                  <memory id="ATCM" access="rwx" start="0x00000000" size="0x80000"/>
+                </device>
+                <device Dname="BigEndian">
+                  <processor Dendian="Big-endian"/>
+                </device>
+              </family>
+              <family Dfamily="Family">
+                <processor Dcore="Cortex-M4"/>
+                <device Dname="M4"/>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        let processor = &devices.0["R7S910018"].processors[0];
+        assert_eq!(processor.endian, Endian::Configurable);
+        assert_eq!(processor.endian.to_string(), "Configurable");
+        assert_eq!(devices.0["BigEndian"].processors[0].endian, Endian::Big);
+        assert_eq!(devices.0["M4"].processors[0].endian, Endian::Little);
+        assert_eq!(Endian::from_str("Big-endian").unwrap(), Endian::Big);
+        assert!(Endian::from_str("Middle-endian").is_err());
+    }
+
+    #[test]
+    fn capture_extra_attributes() {
+        let xml = r#"<devices>
+              <family Dfamily="Family" Dvendor="Vendor:1" Dfuture="family">
+                <processor Dcore="Cortex-M33" Dfuture="processor" Dclock="48000000"/>
+                <memory id="IROM1" start="0x0" size="0x1000" uninit="1"/>
+                <device Dname="Device" Dfuture="device">
+                  <processor Dclock="64000000"/>
+                </device>
+              </family>
+            </devices>"#;
+        let plain = Devices::from_string(xml).unwrap();
+        let device = &plain.0["Device"];
+        assert!(device.extra_attributes.is_empty());
+        assert!(device.processors[0].extra_attributes.is_empty());
+        assert!(device.memories.0["IROM1"].extra_attributes.is_empty());
+
+        let opts = ParseOptions {
+            capture_extra: true,
+            ..Default::default()
+        };
+        let captured = Devices::from_string_with(xml, &opts).unwrap();
+        let device = &captured.0["Device"];
+        assert_eq!(device.extra_attributes.len(), 1);
+        assert_eq!(device.extra_attributes["Dfuture"], "device");
+        let processor = &device.processors[0];
+        assert_eq!(processor.extra_attributes.len(), 2);
+        assert_eq!(processor.extra_attributes["Dfuture"], "processor");
+        assert_eq!(processor.extra_attributes["Dclock"], "64000000");
+        let memory = &device.memories.0["IROM1"];
+        assert_eq!(memory.extra_attributes.len(), 1);
+        assert_eq!(memory.extra_attributes["uninit"], "1");
+    }
+
+    #[test]
+    fn path_styles() {
+        let xml = r#"<devices>
+              <family Dfamily="Family">
+                <processor Dcore="Cortex-M4"/>
+                <debug svd="SVD\Device.svd"/>
+                <algorithm name="Flash\Main.FLM" start="0x08000000" size="0x100000"/>
+                <device Dname="Device">
+                  <algorithm name="Flash/OTP.FLM" start="0x1FFF7800" size="0x200"/>
+                </device>
+              </family>
+            </devices>"#;
+        let paths = |path_style| {
+            let opts = ParseOptions {
+                path_style,
+                ..Default::default()
+            };
+            let mut devices = Devices::from_string_with(xml, &opts).unwrap();
+            let device = devices.0.remove("Device").unwrap();
+            let mut paths: Vec<String> = device
+                .algorithms
+                .iter()
+                .map(|a| a.file_name.to_string_lossy().into_owned())
+                .collect();
+            paths.extend(device.processors[0].svd.clone());
+            paths
+        };
+        assert_eq!(
+            paths(PathStyle::Posix),
+            ["Flash/Main.FLM", "Flash/OTP.FLM", "SVD/Device.svd"]
+        );
+        assert_eq!(
+            paths(PathStyle::Raw),
+            ["Flash\\Main.FLM", "Flash/OTP.FLM", "SVD\\Device.svd"]
+        );
+        let sep = std::path::MAIN_SEPARATOR;
+        assert_eq!(
+            paths(PathStyle::Native),
+            [
+                format!("Flash{sep}Main.FLM"),
+                format!("Flash{sep}OTP.FLM"),
+                format!("SVD{sep}Device.svd")
+            ]
+        );
+    }
+
+    #[test]
+    fn device_description_falls_back_to_family() {
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="Family">
+                <description>
+                  The family
+                  description.
+                </description>
+                <processor Dcore="Cortex-M4"/>
+                <device Dname="Described">
+                  <description>  A   device
+                    with its own description. </description>
+                </device>
+                <device Dname="Undescribed"/>
+                <device Dname="Base">
+                  <description>The base device.</description>
+                  <variant Dvariant="Variant"/>
+                </device>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        let description = |name: &str| devices.0[name].description.as_deref();
+        assert_eq!(
+            description("Described"),
+            Some("A device with its own description.")
+        );
+        assert_eq!(description("Undescribed"), Some("The family description."));
+        assert_eq!(description("Variant"), Some("The base device."));
+    }
+
+    #[test]
+    fn rename_device() {
+        let mut devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="Family">
+                <processor Dcore="Cortex-M4"/>
+                <device Dname="A"/>
+                <device Dname="B"/>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        assert!(devices.rename("A", "C".into()));
+        assert!(!devices.0.contains_key("A"));
+        assert_eq!(devices.0["C"].name, "C");
+
+        assert!(!devices.rename("Missing", "D".into()));
+        assert!(!devices.rename("C", "B".into()));
+        assert_eq!(devices.0["C"].name, "C");
+        assert_eq!(devices.0["B"].name, "B");
+        assert!(devices.rename("B", "B".into()));
+    }
+
+    #[test]
+    fn processors_merge_with_parent_in_order() {
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="Family">
+                <processor Pname="cm7" Dcore="Cortex-M7" Dfpu="DP_FPU"/>
+                <processor Pname="cm4" Dcore="Cortex-M4"/>
+                <device Dname="Device">
+                  <processor Pname="cm4" Dfpu="SP_FPU"/>
+                </device>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        let processors = &devices.0["Device"].processors;
+        let names: Vec<_> = processors.iter().map(|p| p.name.as_deref()).collect();
+        assert_eq!(names, vec![Some("cm4"), Some("cm7")]);
+        assert!(matches!(processors[0].core, Core::CortexM4));
+        assert!(matches!(processors[0].fpu, FPU::SinglePrecision));
+        assert!(matches!(processors[1].fpu, FPU::DoublePrecision));
+    }
+
+    #[test]
+    fn algorithm_ram_window() {
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="Family">
+                <processor Pname="cm7" Dcore="Cortex-M7"/>
+                <processor Pname="cm4" Dcore="Cortex-M4"/>
+                <memory id="FLASH" access="rx" start="0x08000000" size="0x100000" startup="1"/>
+                <memory id="SRAM1" access="rw" start="0x30000000" size="0x20000" default="1"/>
+                <memory id="AXI_SRAM" access="rw" start="0x24000000" size="0x80000" default="1"/>
+                <memory id="SRAM4" access="rw" start="0x38000000" size="0x10000"/>
+                <memory id="DTCM" Pname="cm7" access="rw" start="0x20000000" size="0x20000" default="1"/>
+                <memory id="CM4_RAM" Pname="cm4" access="rw" start="0x10000000" size="0x8000" default="1"/>
+                <algorithm name="Flash/Explicit.FLM" start="0x08000000" size="0x100000"
+                           RAMstart="0x20000000" RAMsize="0x1000"/>
+                <algorithm name="Flash/Any.FLM" start="0x08000000" size="0x100000"/>
+                <algorithm name="Flash/CM4.FLM" Pname="cm4" start="0x08000000" size="0x100000"/>
+                <device Dname="Device"/>
+              </family>
+              <family Dfamily="No RAM">
+                <processor Dcore="Cortex-M0"/>
+                <memory id="FLASH" access="rx" start="0x08000000" size="0x100000" startup="1"/>
+                <memory id="ROM" access="r" start="0x1FFF0000" size="0x8000"/>
+                <algorithm name="Flash/Explicit.FLM" start="0x08000000" size="0x100000"
+                           RAMstart="0x20000000" RAMsize="0x1000"/>
+                <algorithm name="Flash/Any.FLM" start="0x08000000" size="0x100000"/>
+                <device Dname="NoRam"/>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        let device = &devices.0["Device"];
+        let ram = |name: &str, device: &Device| {
+            device
+                .algorithms
+                .iter()
+                .find(|a| a.file_name.ends_with(name))
+                .unwrap()
+                .resolve_ram(&device.memories)
+        };
+        assert_eq!(ram("Explicit.FLM", device), Some((0x2000_0000, 0x1000)));
+        assert_eq!(ram("Any.FLM", device), Some((0x2400_0000, 0x8_0000)));
+        assert_eq!(ram("CM4.FLM", device), Some((0x1000_0000, 0x8000)));
+
+        // Without any writable region, only the RAM the pack gives explicitly is known.
+        let no_ram = &devices.0["NoRam"];
+        assert!(no_ram.memories.writable().is_empty());
+        assert_eq!(ram("Explicit.FLM", no_ram), Some((0x2000_0000, 0x1000)));
+        assert_eq!(ram("Any.FLM", no_ram), None);
+    }
+
+    #[test]
+    fn algorithm_default_for_region() {
+        let algorithm = |default| Algorithm {
+            file_name: "Flash/Main.FLM".into(),
+            start: 0x0800_0000,
+            size: 0x10_0000,
+            ram_start: None,
+            ram_size: None,
+            default,
+            style: AlgorithmStyle::Keil,
+            p_name: None,
+            condition: None,
+        };
+        let region = |start, size| Memory {
+            name: "FLASH".into(),
+            p_name: None,
+            p_unit: None,
+            condition: None,
+            access: MemoryPermissions::from_str("rx"),
+            start,
+            size,
+            startup: false,
+            default: false,
+            extra_attributes: HashMap::new(),
+        };
+        let default = algorithm(true);
+        assert!(default.is_default_for(&region(0x0800_0000, 0x10_0000)));
+        assert!(default.is_default_for(&region(0x080F_0000, 0x1_0000)));
+        assert!(!default.is_default_for(&region(0x080F_0000, 0x1_0001)));
+        assert!(!default.is_default_for(&region(0x07FF_FFFF, 0x10)));
+        assert!(!algorithm(false).is_default_for(&region(0x0800_0000, 0x10_0000)));
+    }
+
+    #[test]
+    fn secure_algorithm_for_non_secure_flash() {
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="STM32L5">
+                <processor Dcore="Cortex-M33"/>
+                <memory name="FLASH_NS" access="rxn" start="0x08000000" size="0x80000"/>
+                <memory name="FLASH_S" access="rxs" start="0x0C000000" size="0x80000" startup="1"/>
+                <memory name="OTP_NS" access="rxn" start="0x0BFA0000" size="0x200"/>
+                <memory name="SRAM_NS" access="rwxn" start="0x20000000" size="0x40000"/>
+                <memory name="SRAM_S" access="rwxs" start="0x30000000" size="0x40000"/>
+                <algorithm name="Flash/L5_NS.FLM" start="0x08000000" size="0x80000" default="1"/>
+                <algorithm name="Flash/L5_S.FLM" start="0x0C000000" size="0x80000" default="1"/>
+                <device Dname="STM32L552ZE"/>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        let device = &devices.0["STM32L552ZE"];
+        let region = |name: &str| &device.memories.0[name];
+        let algorithm = |name: &str| {
+            device
+                .secure_flash_algorithm(region(name))
+                .map(|alg| alg.file_name.to_str().unwrap())
+        };
+
+        let alias = |name: &str| {
+            device
+                .secure_alias(region(name))
+                .map(|mem| mem.name.as_str())
+        };
+
+        assert_eq!(alias("FLASH_NS"), Some("FLASH_S"));
+        assert_eq!(alias("SRAM_NS"), Some("SRAM_S"));
+        assert_eq!(alias("FLASH_S"), None);
+        assert_eq!(alias("OTP_NS"), None);
+
+        assert_eq!(algorithm("FLASH_NS"), Some("Flash/L5_S.FLM"));
+        assert_eq!(algorithm("FLASH_S"), Some("Flash/L5_S.FLM"));
+        assert_eq!(algorithm("OTP_NS"), None);
+    }
+
+    #[test]
+    fn flash_banks_with_algorithms() {
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="Family">
+                <processor Dcore="Cortex-M4"/>
+                <memory name="BANK2" access="rx" start="0x08080000" size="0x80000"/>
+                <memory name="BANK1" access="rx" start="0x08000000" size="0x80000" startup="1"/>
+                <memory name="OTP" access="rx" start="0x1FFF7800" size="0x200"/>
+                <memory name="SRAM" access="rwx" start="0x20000000" size="0x20000"/>
+                <algorithm name="Flash/Dual.FLM" start="0x08000000" size="0x100000" default="1"/>
+                <device Dname="Device"/>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        let banks = devices.0["Device"].flash_banks();
+        let summary: Vec<(&str, u64, Option<&str>)> = banks
+            .iter()
+            .map(|bank| {
+                (
+                    bank.region.name.as_str(),
+                    bank.region.size,
+                    bank.algorithm.as_ref().and_then(|a| a.file_name.to_str()),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("BANK1", 0x8_0000, Some("Flash/Dual.FLM")),
+                ("BANK2", 0x8_0000, Some("Flash/Dual.FLM")),
+                ("OTP", 0x200, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn primary_algorithm_prefers_keil() {
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="Family">
+                <processor Dcore="Cortex-M4"/>
+                <memory id="IROM1" start="0x08000000" size="0x80000" startup="1"/>
+                <memory id="IROM2" start="0x08080000" size="0x80000"/>
+                <device Dname="Device">
+                  <algorithm name="Flash/Main.elf" style="CMSIS" start="0x08000000" size="0x80000" default="1"/>
+                  <algorithm name="Flash/Main.FLM" start="0x08000000" size="0x80000" default="1"/>
+                  <algorithm name="Flash/Bank2.elf" style="CMSIS" start="0x08080000" size="0x80000" default="1"/>
+                  <algorithm name="Flash/Bank2.FLM" start="0x08080000" size="0x80000"/>
+                </device>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        let device = &devices.0["Device"];
+        let primary = |region: &str| {
+            device
+                .primary_algorithm(&device.memories.0[region])
+                .map(|alg| alg.file_name.to_str().unwrap())
+        };
+        assert_eq!(primary("IROM1"), Some("Flash/Main.FLM"));
+        // The style outweighs being marked default.
+        assert_eq!(primary("IROM2"), Some("Flash/Bank2.FLM"));
+    }
+
+    #[test]
+    fn external_flash_algorithm_runs_from_internal_ram() {
+        // Modeled on the i.MX RT1060: the boot flash is QSPI behind FlexSPI, programmed by an
+        // algorithm that runs from on-chip DTCM.
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="MIMXRT1062">
+                <processor Dcore="Cortex-M7" Dfpu="DP_FPU"/>
+                <memory name="BOARD_FLASH" access="rx" start="0x60000000" size="0x800000" startup="1"/>
+                <memory name="SRAM_ITC" access="rwx" start="0x00000000" size="0x20000"/>
+                <memory name="SRAM_DTC" access="rw" start="0x20000000" size="0x20000" default="1"/>
+                <memory name="SRAM_OC" access="rw" start="0x20200000" size="0xC0000"/>
+                <device Dname="MIMXRT1062xxxxA">
+                  <algorithm name="arm/MIMXRT106x_QSPI_4KB_SEC.FLM" start="0x60000000" size="0x800000"
+                             RAMstart="0x20000000" RAMsize="0x8000" default="1"/>
+                </device>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        let device = &devices.0["MIMXRT1062xxxxA"];
+        let primary = device
+            .primary_algorithm(&device.memories.0["BOARD_FLASH"])
+            .unwrap();
+        assert_eq!(
+            primary.file_name,
+            Path::new("arm/MIMXRT106x_QSPI_4KB_SEC.FLM")
+        );
+        assert_eq!(primary.ram_start, Some(0x2000_0000));
+        assert!(device
+            .primary_algorithm(&device.memories.0["SRAM_DTC"])
+            .is_none());
+
+        let banks = device.flash_banks();
+        assert_eq!(banks.len(), 1);
+        assert_eq!(banks[0].region.name, "BOARD_FLASH");
+        assert_eq!(banks[0].algorithm.as_ref(), Some(primary));
+        assert_eq!(device.validate(), vec![]);
+    }
+
+    #[test]
+    fn primary_algorithm_selection() {
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="Family">
+                <processor Dcore="Cortex-M4"/>
+                <memory id="IROM1" start="0x08000000" size="0x80000" startup="1"/>
+                <memory id="IROM2" start="0x08080000" size="0x80000"/>
+                <memory id="OTP" start="0x1FFF7800" size="0x200"/>
+                <memory id="EEPROM" start="0x08080000" size="0x1000"/>
+                <memory id="IROM3" start="0x08100000" size="0x80000"/>
+                <algorithm name="Flash/Family.FLM" start="0x08000000" size="0x180000" default="1"/>
+                <device Dname="Device">
+                  <algorithm name="Flash/Bank2.FLM" start="0x08080000" size="0x80000"/>
+                  <algorithm name="Flash/First.FLM" start="0x08000000" size="0x100000" default="1"/>
+                  <algorithm name="Flash/Second.FLM" start="0x08000000" size="0x100000" default="1"/>
+                  <algorithm name="Flash/Upper.FLM" start="0x08080000" size="0x100000" default="1"/>
+                </device>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        let device = &devices.0["Device"];
+        let primary = |region: &str| {
+            device
+                .primary_algorithm(&device.memories.0[region])
+                .map(|alg| alg.file_name.to_str().unwrap())
+        };
+        assert_eq!(primary("IROM1"), Some("Flash/First.FLM"));
+        assert_eq!(primary("IROM2"), Some("Flash/First.FLM"));
+        assert_eq!(primary("EEPROM"), Some("Flash/First.FLM"));
+        assert_eq!(primary("OTP"), None);
+        // Starting lower outweighs being declared by the innermost element.
+        assert_eq!(primary("IROM3"), Some("Flash/Family.FLM"));
+    }
+
+    #[test]
+    fn inherited_algorithm_removed() {
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="Family">
+                <processor Dcore="Cortex-M4"/>
+                <algorithm name="Flash\Bank1.FLM" start="0x08000000" size="0x80000" default="1"/>
+                <algorithm name="Flash\Bank2.FLM" start="0x08080000" size="0x80000" default="1"/>
+                <algorithm name="Flash/OTP.FLM" start="0x1FFF7800" size="0x200"/>
+                <subFamily DsubFamily="Single bank">
+                  <algorithm name="Flash\Bank2.FLM" remove="1"/>
+                  <device Dname="Small"/>
+                  <device Dname="Tiny">
+                    <algorithm name="Flash/OTP.FLM" remove="1"/>
+                  </device>
+                </subFamily>
+                <device Dname="Large"/>
+                <device Dname="Patched">
+                  <algorithm name="Flash/Bank2.FLM" remove="1"/>
+                  <algorithm name="Flash/Bank2_v2.FLM" start="0x08080000" size="0x80000" default="1"/>
+                </device>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        let files = |name: &str| -> Vec<&str> {
+            devices.0[name]
+                .algorithms
+                .iter()
+                .map(|alg| alg.file_name.to_str().unwrap())
+                .collect()
+        };
+        assert_eq!(
+            files("Large"),
+            ["Flash/Bank1.FLM", "Flash/Bank2.FLM", "Flash/OTP.FLM"]
+        );
+        assert_eq!(files("Small"), ["Flash/Bank1.FLM", "Flash/OTP.FLM"]);
+        assert_eq!(files("Tiny"), ["Flash/Bank1.FLM"]);
+        assert_eq!(
+            files("Patched"),
+            ["Flash/Bank1.FLM", "Flash/Bank2_v2.FLM", "Flash/OTP.FLM"]
+        );
+    }
+
+    #[test]
+    fn algorithms_deduplicated_and_sorted() {
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="Family">
+                <processor Dcore="Cortex-M4"/>
+                <algorithm name="Flash/OTP.FLM" start="0x1FFF7800" size="0x200"/>
+                <algorithm name="Flash/Main.FLM" start="0x08000000" size="0x100000"/>
+                <algorithm name="Flash/Alt.FLM" start="0x08000000" size="0x100000"/>
+                <device Dname="Device">
+                  <algorithm name="Flash/Main.FLM" start="0x08000000" size="0x100000"
+                             default="1" RAMstart="0x20000000" RAMsize="0x1000"/>
+                </device>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        let algorithms = &devices.0["Device"].algorithms;
+        let files: Vec<&str> = algorithms
+            .iter()
+            .map(|alg| alg.file_name.to_str().unwrap())
+            .collect();
+        assert_eq!(files, ["Flash/Main.FLM", "Flash/Alt.FLM", "Flash/OTP.FLM"]);
+        assert!(algorithms[0].default);
+        assert_eq!(algorithms[0].ram_start, Some(0x2000_0000));
+    }
+
+    #[test]
+    fn nvm_regions() {
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="Family">
+                <processor Dcore="Cortex-M7"/>
+                <memory id="IROM1" start="0x08000000" size="0x100000" startup="1"/>
+                <memory name="EXTFLASH" access="rw" start="0x70000000" size="0x800000"/>
+                <memory name="EEPROM" access="rw" start="0x08080000" size="0x1000"/>
+                <memory name="OTP" access="r" start="0x1FF00000" size="0x400"/>
+                <memory name="ITCM" access="rwx" start="0x00000000" size="0x10000"/>
+                <memory id="IRAM1" start="0x20000000" size="0x20000"/>
+                <memory name="PERIPH" access="rwp" start="0x40000000" size="0x10000000"/>
+                <device Dname="Device">
+                  <algorithm name="Flash/Whole.FLM" start="0x00000000" size="0x80000000" default="1"/>
+                </device>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        let device = &devices.0["Device"];
+        let nvm = |name: &str| device.memories.0[name].is_nvm();
+        assert!(nvm("IROM1"));
+        assert!(nvm("EXTFLASH"));
+        assert!(nvm("EEPROM"));
+        assert!(nvm("OTP"));
+        assert!(!nvm("ITCM"));
+        assert!(!nvm("IRAM1"));
+        assert!(!nvm("PERIPH"));
+
+        // The algorithm covers every region, but is only paired with the non-volatile ones.
+        let primary = |name: &str| device.primary_algorithm(&device.memories.0[name]).is_some();
+        assert!(primary("IROM1"));
+        assert!(primary("EEPROM"));
+        assert!(!primary("IRAM1"));
+        assert!(!primary("ITCM"));
+    }
+
+    #[test]
+    fn executable_regions_by_name() {
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="Family">
+                <processor Dcore="Cortex-M7"/>
+                <memory name="ITCM" access="rw" start="0x00000000" size="0x10000"/>
+                <memory id="IROM1" start="0x08000000" size="0x100000" default="1"/>
+                <memory name="EXTFLASH" access="rw" start="0x70000000" size="0x800000"/>
+                <memory name="QSPI" access="rw" start="0x90000000" size="0x1000000"/>
+                <memory id="IRAM1" start="0x20000000" size="0x20000"/>
+                <memory name="QSPI_REGS" access="rwp" start="0xA0001000" size="0x400"/>
+                <memory name="DATA_EEPROM" access="rw" start="0x08080000" size="0x1000"/>
+                <device Dname="Device"/>
+                <device Dname="XipBoot">
+                  <memory name="QSPI" access="rw" start="0x90000000" size="0x1000000" startup="1"/>
+                </device>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        let device = &devices.0["Device"];
+        let executable = |name: &str| device.memories.0[name].is_executable();
+        assert!(executable("ITCM"));
+        assert!(executable("IROM1"));
+        assert!(executable("EXTFLASH"));
+        assert!(executable("QSPI"));
+        assert!(!executable("IRAM1"));
+        assert!(!executable("QSPI_REGS"));
+        assert!(!executable("DATA_EEPROM"));
+        assert!(device.memories.0["DATA_EEPROM"].is_nvm());
+
+        assert_eq!(device.total_flash(), 0x100000 + 0x800000 + 0x1000000);
+        assert_eq!(device.default_rom().unwrap().name, "IROM1");
+        assert_eq!(devices.0["XipBoot"].default_rom().unwrap().name, "QSPI");
+    }
+
+    #[test]
+    fn memories_grouped_by_kind() {
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="Family">
+                <processor Dcore="Cortex-M7"/>
+                <memory name="PERIPH" access="rwp" start="0x40000000" size="0x20000000"/>
+                <memory id="IRAM2" start="0x30000000" size="0x48000"/>
+                <memory id="IRAM1" start="0x24000000" size="0x80000"/>
+                <memory name="DTCM" access="rw" start="0x20000000" size="0x20000"/>
+                <memory name="ITCM" access="rwx" start="0x00000000" size="0x10000"/>
+                <memory id="IROM2" start="0x08100000" size="0x100000"/>
+                <memory id="IROM1" start="0x08000000" size="0x100000"/>
+                <memory name="RESERVED" access="" start="0x60000000" size="0x1000"/>
+                <device Dname="Device"/>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        let groups: Vec<(MemoryKind, Vec<&str>)> = devices.0["Device"]
+            .memories_by_kind()
+            .into_iter()
+            .map(|(kind, mems)| (kind, mems.iter().map(|m| m.name.as_str()).collect()))
+            .collect();
+        assert_eq!(
+            groups,
+            vec![
+                (MemoryKind::Rom, vec!["IROM1", "IROM2"]),
+                (MemoryKind::Ram, vec!["IRAM1", "IRAM2"]),
+                (MemoryKind::Tcm, vec!["ITCM", "DTCM"]),
+                (MemoryKind::Peripheral, vec!["PERIPH"]),
+                (MemoryKind::Other, vec!["RESERVED"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn duplicate_variant_names_keep_last() {
+        let xml = r#"<devices>
+              <family Dfamily="Family">
+                <processor Dcore="Cortex-M4"/>
+                <device Dname="Device">
+                  <variant Dvariant="Device_A" Dvendor="First:1"/>
+                  <variant Dvariant="Device_A" Dvendor="Second:2"/>
+                  <variant Dvariant="Device_B"/>
+                  <variant Dvariant="Device_A" Dvendor="Third:3"/>
+                </device>
+              </family>
+            </devices>"#;
+        let mut diags: Vec<Diagnostic> = Vec::new();
+        let devices =
+            Devices::from_string_with_diagnostics(xml, &ParseOptions::default(), &mut diags)
+                .unwrap();
+        assert_eq!(devices.0.len(), 2);
+        assert_eq!(devices.0["Device_A"].vendor_name(), Some("Third"));
+        assert!(devices.0.contains_key("Device_B"));
+        let dropped: Vec<&str> = diags.iter().map(|d| &xml[d.range.clone()]).collect();
+        assert_eq!(
+            dropped,
+            [
+                r#"<variant Dvariant="Device_A" Dvendor="First:1"/>"#,
+                r#"<variant Dvariant="Device_A" Dvendor="Second:2"/>"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn startup_memory_per_processor() {
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="Family">
+                <processor Pname="cm7" Dcore="Cortex-M7"/>
+                <processor Pname="cm4" Dcore="Cortex-M4"/>
+                <memory id="FLASH" Pname="cm7" start="0x08000000" size="0x100000" startup="1"/>
+                <memory id="SRAM" Pname="cm4" start="0x10000000" size="0x48000" startup="1"/>
+                <memory id="SHARED" start="0x38000000" size="0x10000"/>
+                <device Dname="Dual"/>
+              </family>
+              <family Dfamily="Single">
+                <processor Dcore="Cortex-M4"/>
+                <memory id="IROM1" start="0x08000000" size="0x100000" startup="1"/>
+                <device Dname="Single"/>
+              </family>
+              <family Dfamily="Units">
+                <processor Dcore="Cortex-M0+" Punits="2"/>
+                <memory id="BOOT0" Punit="0" start="0x10000000" size="0x1000" startup="1"/>
+                <memory id="BOOT1" Punit="1" start="0x00000000" size="0x1000" startup="1"/>
+                <device Dname="Units"/>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        let dual = &devices.0["Dual"];
+        let startup = |pname: &str| {
+            let processor = dual
+                .processors
+                .iter()
+                .find(|p| p.name.as_deref() == Some(pname))
+                .unwrap();
+            dual.startup_memory(processor).map(|m| m.name.as_str())
+        };
+        assert_eq!(startup("cm7"), Some("FLASH"));
+        assert_eq!(startup("cm4"), Some("SRAM"));
+
+        let single = &devices.0["Single"];
+        let startup = single.startup_memory(&single.processors[0]).unwrap();
+        assert_eq!(startup.name, "IROM1");
+
+        // Each unit boots from the region scoped to it alone.
+        let units = &devices.0["Units"];
+        let startup: Vec<&str> = units
+            .processors
+            .iter()
+            .map(|p| units.startup_memory(p).unwrap().name.as_str())
+            .collect();
+        assert_eq!(startup, vec!["BOOT0", "BOOT1"]);
+        assert_eq!(units.validate(), vec![]);
+    }
+
+    #[test]
+    fn absurd_punits_are_clamped() {
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="Family">
+                <processor Dcore="Cortex-M4" Punits="4000000000"/>
+                <device Dname="Many"/>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        let processors = &devices.0["Many"].processors;
+        assert_eq!(processors.len(), MAX_PROCESSOR_UNITS);
+        assert_eq!(processors.last().unwrap().unit, MAX_PROCESSOR_UNITS - 1);
+    }
+
+    #[test]
+    fn merge_prefers_newer_pack() {
+        let pack = |flash: &str| {
+            Devices::from_string(&format!(
+                r#"<devices>
+                  <family Dfamily="Family">
+                    <processor Dcore="Cortex-M4"/>
+                    <memory id="IROM1" start="0x08000000" size="{}"/>
+                    <device Dname="Shared"/>
+                  </family>
+                </devices>"#,
+                flash
+            ))
+            .unwrap()
+        };
+        let flash = |devices: &Devices| devices.0["Shared"].total_flash();
+
+        let mut devices = Devices::default();
+        devices.merge_preferring(pack("0x10000"), "1.2.0");
+        devices.merge_preferring(pack("0x20000"), "1.3.0");
+        assert_eq!(flash(&devices), 0x20000);
+        assert_eq!(devices.0["Shared"].pack_version.as_deref(), Some("1.3.0"));
+
+        devices.merge_preferring(pack("0x10000"), "1.2.0");
+        assert_eq!(flash(&devices), 0x20000);
+
+        devices.merge_preferring(pack("0x30000"), "1.3.0");
+        assert_eq!(flash(&devices), 0x20000);
+
+        let mut unversioned = pack("0x10000");
+        unversioned.merge_preferring(pack("0x20000"), "1.2.0");
+        assert_eq!(flash(&unversioned), 0x20000);
+    }
+
+    #[test]
+    fn collapsed_variants() {
+        let xml = r#"<devices>
+              <family Dfamily="STM32F4">
+                <processor Dcore="Cortex-M4"/>
+                <device Dname="STM32F407">
+                  <memory id="IROM1" start="0x08000000" size="0x100000"/>
+                  <variant Dvariant="STM32F407VGT6"/>
+                  <variant Dvariant="STM32F407VGT7"/>
+                  <variant Dvariant="STM32F407VGH6"/>
+                  <variant Dvariant="STM32F407VET6"/>
+                </device>
+              </family>
+            </devices>"#;
+        let plain = Devices::from_string(xml).unwrap();
+        assert_eq!(plain.0.len(), 4);
+        assert!(plain.0.values().all(|d| d.variants.is_empty()));
+
+        let opts = ParseOptions {
+            collapse_variants: true,
+            ..Default::default()
+        };
+        let collapsed = Devices::from_string_with(xml, &opts).unwrap();
+        let mut names: Vec<&str> = collapsed.0.keys().map(String::as_str).collect();
+        names.sort();
+        assert_eq!(names, vec!["STM32F407VET6", "STM32F407VG"]);
+        let device = &collapsed.0["STM32F407VG"];
+        assert_eq!(
+            device.variants,
+            vec!["STM32F407VGT6", "STM32F407VGT7", "STM32F407VGH6"]
+        );
+        assert_eq!(device.base_device.as_deref(), Some("STM32F407"));
+        assert_eq!(device.total_flash(), 0x10_0000);
+        assert!(collapsed.0["STM32F407VET6"].variants.is_empty());
+    }
+
+    #[test]
+    fn first_declared_processor_boots() {
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="Family">
+                <processor Pname="cm7" Dcore="Cortex-M7"/>
+                <processor Pname="cm4" Dcore="Cortex-M4" Punits="2"/>
+                <device Dname="Dual"/>
+                <device Dname="Override">
+                  <processor Pname="cm4" Dcorever="r0p1"/>
+                </device>
+              </family>
+              <family Dfamily="Other">
+                <processor Dcore="Cortex-M0+" Punits="2"/>
+                <device Dname="Single"/>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        let boot = |name: &str| {
+            let device = &devices.0[name];
+            let boot: Vec<(Option<&str>, usize)> = device
+                .processors
+                .iter()
+                .filter(|p| p.is_boot_core)
+                .map(|p| (p.name.as_deref(), p.unit))
+                .collect();
+            assert_eq!(boot.len(), 1, "{}", name);
+            (boot[0], device.boot_core().unwrap().core.clone())
+        };
+        assert_eq!(boot("Dual"), ((Some("cm7"), 0), Core::CortexM7));
+        assert_eq!(boot("Override"), ((Some("cm7"), 0), Core::CortexM7));
+        assert_eq!(boot("Single"), ((None, 0), Core::CortexM0Plus));
+    }
+
+    #[test]
+    fn per_unit_svd() {
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="Family">
+                <processor Pname="cl" Dcore="Cortex-A53" Punits="3"/>
+                <debug svd="Cluster.svd"/>
+                <device Dname="Device">
+                  <debug Pname="cl" Punit="0" svd="Unit0.svd"/>
+                  <debug Pname="cl" Punit="1" svd="Unit1.svd"/>
+                </device>
+                <device Dname="SameLevel">
+                  <debug svd="Shared.svd"/>
+                  <debug Pname="cl" Punit="1" svd="Unit1.svd"/>
+                </device>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        let svds = |name: &str| -> Vec<Option<String>> {
+            devices.0[name]
+                .processors
+                .iter()
+                .map(|p| p.svd.clone())
+                .collect()
+        };
+        assert_eq!(
+            svds("Device"),
+            vec![
+                Some("Unit0.svd".into()),
+                Some("Unit1.svd".into()),
+                Some("Cluster.svd".into())
+            ]
+        );
+        assert_eq!(
+            svds("SameLevel"),
+            vec![
+                Some("Shared.svd".into()),
+                Some("Unit1.svd".into()),
+                Some("Shared.svd".into())
+            ]
+        );
+    }
+
+    #[test]
+    fn shared_svd_fallback() {
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="STM32H7">
+                <processor Pname="CM7" Dcore="Cortex-M7"/>
+                <processor Pname="CM4" Dcore="Cortex-M4"/>
+                <debug Pname="CM7" svd="CM7.svd"/>
+                <device Dname="STM32H745">
+                  <debug svd="Shared.svd"/>
+                </device>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        let device = &devices.0["STM32H745"];
+        let svd = |name: &str| {
+            device
+                .processors
+                .iter()
+                .find(|p| p.name.as_deref() == Some(name))
+                .and_then(|p| p.svd.as_deref())
+        };
+        assert_eq!(svd("CM7"), Some("CM7.svd"));
+        assert_eq!(svd("CM4"), Some("Shared.svd"));
+    }
+
+    #[test]
+    fn content_hash_tracks_changes() {
+        let parse = |memories: &str, algorithm_size: &str| {
+            let xml = format!(
+                r#"<devices>
+                  <family Dfamily="Family">
+                    <processor Dcore="Cortex-M4"/>
+                    {}
+                    <algorithm name="Flash/Main.FLM" start="0x08000000" size="{}"/>
+                    <device Dname="Device"/>
+                  </family>
+                </devices>"#,
+                memories, algorithm_size
+            );
+            Devices::from_string(&xml)
+                .unwrap()
+                .0
+                .remove("Device")
+                .unwrap()
+        };
+        let flash = r#"<memory id="IROM1" start="0x08000000" size="0x100000"/>"#;
+        let ram = r#"<memory id="IRAM1" start="0x20000000" size="0x20000"/>"#;
+        let device = parse(&format!("{}{}", flash, ram), "0x100000");
+
+        let reordered = parse(&format!("{}{}", ram, flash), "0x100000");
+        assert_eq!(device.content_hash(), reordered.content_hash());
+
+        let algorithm_changed = parse(&format!("{}{}", flash, ram), "0x80000");
+        assert_ne!(device.content_hash(), algorithm_changed.content_hash());
+
+        let memory_changed = parse(
+            &format!("{}{}", flash, ram.replace("0x20000\"/>", "0x40000\"/>")),
+            "0x100000",
+        );
+        assert_ne!(device.content_hash(), memory_changed.content_hash());
+    }
+
+    #[test]
+    fn unit_scoped_processors() {
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="Family">
+                <processor Pname="cm0" Dcore="Cortex-M0+" Punits="2"/>
+                <device Dname="PerUnit">
+                  <processor Pname="cm0" Punit="0" Dcore="Cortex-M0+" Dcorever="r0p0"/>
+                  <processor Pname="cm0" Punit="1" Dcore="Cortex-M0+" Dcorever="r0p1"/>
+                </device>
+                <device Dname="Override">
+                  <processor Pname="cm0" Punit="1" Dcorever="r0p1"/>
+                </device>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        let processors = |name: &str| -> Vec<(usize, Option<String>)> {
+            let mut processors: Vec<_> = devices.0[name]
+                .processors
+                .iter()
+                .map(|p| (p.unit, p.core_revision.clone()))
+                .collect();
+            processors.sort();
+            processors
+        };
+        assert_eq!(
+            processors("PerUnit"),
+            vec![(0, Some("r0p0".into())), (1, Some("r0p1".into()))]
+        );
+        assert_eq!(
+            processors("Override"),
+            vec![(0, None), (1, Some("r0p1".into()))]
+        );
+        assert!(devices.0["Override"]
+            .processors
+            .iter()
+            .all(|p| p.core == Core::CortexM0Plus));
+    }
+
+    #[test]
+    fn distinct_cores() {
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="Family">
+                <processor Pname="cm7" Dcore="Cortex-M7"/>
+                <processor Pname="cm4" Dcore="Cortex-M4" Punits="2"/>
+                <device Dname="Dual"/>
+              </family>
+              <family Dfamily="Other">
+                <processor Dcore="Cortex-M0+" Punits="2"/>
+                <device Dname="Single"/>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        assert_eq!(
+            devices.0["Dual"].cores(),
+            vec![Core::CortexM7, Core::CortexM4]
+        );
+        assert_eq!(devices.0["Single"].cores(), vec![Core::CortexM0Plus]);
+    }
+
+    #[test]
+    fn devices_stats() {
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="F4" Dvendor="STMicroelectronics:13">
+                <processor Dcore="Cortex-M4"/>
+                <memory id="IROM1" start="0x08000000" size="0x100000"/>
+                <memory id="IRAM1" start="0x20000000" size="0x20000"/>
+                <device Dname="STM32F407VG"/>
+                <device Dname="STM32F411RE"/>
+              </family>
+              <family Dfamily="H7" Dvendor="STMicroelectronics:13">
+                <processor Pname="cm7" Dcore="Cortex-M7"/>
+                <processor Pname="cm4" Dcore="Cortex-M4" Punits="2"/>
+                <memory id="IROM1" start="0x08000000" size="0x200000"/>
+                <device Dname="STM32H745"/>
+              </family>
+              <family Dfamily="nRF52" Dvendor="Nordic Semiconductor:54">
+                <processor Dcore="Cortex-M4"/>
+                <memory id="IRAM1" start="0x20000000" size="0x10000"/>
+                <device Dname="nRF52832_xxAA"/>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        let stats = devices.stats();
+        assert_eq!(stats.device_count, 4);
+        assert_eq!(stats.vendor_count, 2);
+        assert_eq!(stats.core_histogram.len(), 2);
+        assert_eq!(stats.core_histogram[&Core::CortexM4], 4);
+        assert_eq!(stats.core_histogram[&Core::CortexM7], 1);
+        assert_eq!(stats.total_flash_bytes, 2 * 0x100000 + 0x200000);
+        assert_eq!(stats.total_ram_bytes, 2 * 0x20000 + 0x10000);
+    }
+
+    #[test]
+    fn vendors_without_id_suffix() {
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="F4" Dvendor="STMicroelectronics:13">
+                <processor Dcore="Cortex-M4"/>
+                <device Dname="STM32F407VG"/>
+                <device Dname="STM32F411RE"/>
+              </family>
+              <family Dfamily="nRF52" Dvendor="Nordic Semiconductor:54">
+                <processor Dcore="Cortex-M4"/>
+                <device Dname="nRF52832_xxAA"/>
+              </family>
+              <family Dfamily="Other" Dvendor="Vendor">
+                <processor Dcore="Cortex-M0"/>
+                <device Dname="Plain"/>
+              </family>
+              <family Dfamily="None">
+                <processor Dcore="Cortex-M0"/>
+                <device Dname="Anonymous"/>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        assert_eq!(
+            devices.0["STM32F407VG"].vendor_name(),
+            Some("STMicroelectronics")
+        );
+        assert_eq!(devices.0["Anonymous"].vendor_name(), None);
+        assert_eq!(
+            devices.vendors().into_iter().collect::<Vec<_>>(),
+            ["Nordic Semiconductor", "STMicroelectronics", "Vendor"]
+        );
+    }
+
+    #[test]
+    fn retain_by_vendor_and_core() {
+        let parse = || {
+            Devices::from_string(
+                r#"<devices>
+                  <family Dfamily="F4" Dvendor="STMicroelectronics:13">
+                    <processor Dcore="Cortex-M4"/>
+                    <device Dname="STM32F407VG"/>
+                  </family>
+                  <family Dfamily="H7" Dvendor="STMicroelectronics:13">
+                    <processor Pname="cm7" Dcore="Cortex-M7"/>
+                    <processor Pname="cm4" Dcore="Cortex-M4"/>
+                    <device Dname="STM32H745"/>
+                  </family>
+                  <family Dfamily="nRF52" Dvendor="Nordic Semiconductor:54">
+                    <processor Dcore="Cortex-M4"/>
+                    <device Dname="nRF52832_xxAA"/>
+                  </family>
+                  <family Dfamily="None">
+                    <processor Dcore="Cortex-M7"/>
+                    <device Dname="Anonymous"/>
+                  </family>
+                </devices>"#,
+            )
+            .unwrap()
+        };
+        let names = |devices: &Devices| {
+            let mut names: Vec<String> = devices.0.keys().cloned().collect();
+            names.sort();
+            names
+        };
+
+        let mut devices = parse();
+        devices.retain_vendor("STMicroelectronics");
+        assert_eq!(names(&devices), ["STM32F407VG", "STM32H745"]);
+        let mut devices = parse();
+        devices.retain_vendor("STMicroelectronics:13");
+        assert_eq!(names(&devices), ["STM32F407VG", "STM32H745"]);
+
+        let mut devices = parse();
+        devices.retain_core(&Core::CortexM7);
+        assert_eq!(names(&devices), ["Anonymous", "STM32H745"]);
+
+        devices.retain_vendor("STMicroelectronics");
+        devices.retain(|name, _| name.ends_with('5'));
+        assert_eq!(names(&devices), ["STM32H745"]);
+    }
+
+    #[test]
+    fn list_names_matches_full_parse() {
+        let xml = r#"<devices>
+              <family Dfamily="STM32H7" Dvendor="STMicroelectronics:13">
+                <processor Pname="cm7" Dcore="Cortex-M7"/>
+                <memory id="IROM1" start="0x08000000" size="0x100000"/>
+                <subFamily DsubFamily="STM32H745">
+                  <processor Pname="cm4" Dcore="Cortex-M4"/>
+                  <device Dname="STM32H745@Y">
+                    <variant Dvariant="STM32H745ZI"/>
+                    <variant Dvariant="STM32H745BI"/>
+                  </device>
+                </subFamily>
+                <device Dname="STM32H743ZI"/>
+                <device Dname="STM32H743ZI"/>
+              </family>
+              <family Dfamily="nRF52" Dvendor="Nordic Semiconductor:54">
+                <device Dname="nRF52832_xxAA">
+                  <processor Dcore="Cortex-M4"/>
+                </device>
+              </family>
+            </devices>"#;
+        let listed = Devices::list_names(xml).unwrap();
+        assert_eq!(
+            listed,
+            vec![
+                DeviceMeta {
+                    name: "STM32H745ZI".into(),
+                    family: "STM32H7".into(),
+                    vendor: Some("STMicroelectronics:13".into()),
+                    cores: vec![Core::CortexM4, Core::CortexM7],
+                },
+                DeviceMeta {
+                    name: "STM32H745BI".into(),
+                    family: "STM32H7".into(),
+                    vendor: Some("STMicroelectronics:13".into()),
+                    cores: vec![Core::CortexM4, Core::CortexM7],
+                },
+                DeviceMeta {
+                    name: "STM32H743ZI".into(),
+                    family: "STM32H7".into(),
+                    vendor: Some("STMicroelectronics:13".into()),
+                    cores: vec![Core::CortexM7],
+                },
+                DeviceMeta {
+                    name: "nRF52832_xxAA".into(),
+                    family: "nRF52".into(),
+                    vendor: Some("Nordic Semiconductor:54".into()),
+                    cores: vec![Core::CortexM4],
+                },
+            ]
+        );
+
+        let devices = Devices::from_string(xml).unwrap();
+        let mut full: Vec<&String> = devices.0.keys().collect();
+        full.sort();
+        let mut names: Vec<&String> = listed.iter().map(|meta| &meta.name).collect();
+        names.sort();
+        assert_eq!(names, full);
+        for meta in &listed {
+            let device = &devices.0[&meta.name];
+            assert_eq!(meta.cores, device.cores());
+            assert_eq!(meta.vendor, device.vendor);
+            assert_eq!(meta.family, device.family);
+        }
+
+        let package = format!("<package>{}</package>", xml);
+        assert_eq!(Devices::list_names(&package).unwrap(), listed);
+    }
+
+    #[test]
+    fn devices_as_json_lines() {
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="Family">
+                <processor Dcore="Cortex-M4"/>
+                <memory id="IROM1" start="0x08000000" size="0x10000" startup="1"/>
+                <memory id="IRAM1" start="0x20000000" size="0x4000"/>
+                <device Dname="B"/>
+                <device Dname="A"/>
+                <device Dname="C"/>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        let mut out = Vec::new();
+        devices.write_jsonl(&mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let names: Vec<String> = out
+            .lines()
+            .map(|line| {
+                let value: serde_json::Value = serde_json::from_str(line).unwrap();
+                value["name"].as_str().unwrap().to_string()
             })
-            .map(Devices)
+            .collect();
+        assert_eq!(names, ["A", "B", "C"]);
+
+        let mut again = Vec::new();
+        devices.write_jsonl(&mut again).unwrap();
+        assert_eq!(out.as_bytes(), again.as_slice());
+    }
+
+    #[test]
+    fn device_as_json() {
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="Family" Dvendor="Vendor:1">
+                <processor Dcore="Cortex-M4"/>
+                <memory id="IROM1" start="0x08000000" size="0x10000" startup="1"/>
+                <memory id="IRAM1" start="0x20000000" size="0x4000"/>
+                <memory id="IRAM2" start="0x20004000" size="0x4000"/>
+                <device Dname="Device"/>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        let device = &devices.0["Device"];
+        let json = device.to_json();
+        assert!(!json.contains('\n'));
+        assert!(json.starts_with(r#"{"algorithms":[],"#));
+        let iram1 = json.find(r#""IRAM1""#).unwrap();
+        let iram2 = json.find(r#""IRAM2""#).unwrap();
+        let irom1 = json.find(r#""IROM1""#).unwrap();
+        assert!(iram1 < iram2 && iram2 < irom1);
+        assert_eq!(json, device.to_json());
+
+        let pretty = device.to_json_pretty();
+        assert!(pretty.contains("\n  \"name\": \"Device\""));
+        let compact: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let indented: serde_json::Value = serde_json::from_str(&pretty).unwrap();
+        assert_eq!(compact, indented);
+    }
+
+    #[test]
+    fn flash_profile() {
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="Family">
+                <processor Dcore="Cortex-M4"/>
+                <memory id="FLASH_BANK2" access="rx" start="0x08080000" size="0x80000"/>
+                <memory id="FLASH_BANK1" access="rx" start="0x08000000" size="0x80000" startup="1"/>
+                <memory id="SRAM" access="rwx" start="0x20000000" size="0x20000" default="1"/>
+                <memory id="PERIPH" access="rwp" start="0x40000000" size="0x20000000"/>
+                <algorithm name="Flash/Main.FLM" start="0x08000000" size="0x100000" default="1"/>
+                <algorithm name="Flash/OTP.FLM" start="0x1FFF7800" size="0x200"/>
+                <device Dname="Device"/>
+              </family>
+              <family Dfamily="Family without RAM">
+                <processor Dcore="Cortex-M4"/>
+                <memory id="FLASH" access="rx" start="0x08000000" size="0x80000" startup="1"/>
+                <memory id="SRAM" access="r" start="0x20000000" size="0x20000"/>
+                <algorithm name="Flash/Main.FLM" start="0x08000000" size="0x80000" default="1"/>
+                <device Dname="NoRam"/>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        let profile = devices.0["Device"].flash_profile().unwrap();
+        let regions: Vec<&str> = profile.regions.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(regions, ["FLASH_BANK1", "FLASH_BANK2"]);
+        let algorithms: Vec<&Path> = profile
+            .algorithms
+            .iter()
+            .map(|a| a.file_name.as_path())
+            .collect();
+        assert_eq!(algorithms, [Path::new("Flash/Main.FLM")]);
+        assert_eq!(profile.ram.name, "SRAM");
+
+        let json = profile.to_json();
+        assert!(!json.contains('\n'));
+        assert!(!json.contains("PERIPH"));
+        let parsed: FlashProfile = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, profile);
+
+        assert_eq!(devices.0["NoRam"].flash_profile(), None);
+    }
+
+    #[test]
+    fn format_bytes_binary_units() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1000), "1000 B");
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1024), "1 KB");
+        assert_eq!(format_bytes(1536), "1.5 KB");
+        assert_eq!(format_bytes(3328), "3.25 KB");
+        assert_eq!(format_bytes(192 * 1024), "192 KB");
+        assert_eq!(format_bytes(1024 * 1024 - 1), "1024 KB");
+        assert_eq!(format_bytes(1048576), "1 MB");
+        assert_eq!(format_bytes(1024 * 1024 * 1024), "1 GB");
+        assert_eq!(format_bytes(u64::MAX), "16 EB");
+    }
+
+    #[test]
+    fn memory_security_state() {
+        for (access, state) in [
+            ("rx", SecurityState::Unspecified),
+            ("rxs", SecurityState::Secure),
+            ("rxc", SecurityState::Secure),
+            ("rxn", SecurityState::NonSecure),
+            ("rwsn", SecurityState::Both),
+            ("rwcn", SecurityState::Both),
+        ] {
+            assert_eq!(
+                MemoryPermissions::from_str(access).security(),
+                state,
+                "{}",
+                access
+            );
+        }
+    }
+
+    #[test]
+    fn access_is_case_insensitive() {
+        for (upper, lower) in [
+            ("RX", "rx"),
+            ("RW", "rw"),
+            ("Rx", "rx"),
+            ("RWXSNC", "rwxsnc"),
+        ] {
+            assert_eq!(
+                MemoryPermissions::from_str(upper),
+                MemoryPermissions::from_str(lower),
+                "{}",
+                upper
+            );
+        }
+        let MemElem(_, mem) = MemElem::from_string(
+            r#"<memory name="SRAM" access="RWX" start="0x20000000" size="0x1000"/>"#,
+        )
+        .unwrap();
+        assert!(mem.access.read && mem.access.write && mem.access.execute);
+    }
+
+    #[test]
+    fn memory_access_filters() {
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="Family">
+                <processor Dcore="Cortex-M7"/>
+                <memory name="FLASH" access="rx" start="0x08000000" size="0x100000"/>
+                <memory name="ITCM" access="rw" start="0x00000000" size="0x10000"/>
+                <memory name="SRAM" access="rwx" start="0x20000000" size="0x20000"/>
+                <memory name="BKPSRAM" access="rw" start="0x40024000" size="0x1000"/>
+                <memory name="PERIPH" access="rwp" start="0x40000000" size="0x20000000"/>
+                <memory name="OTP" access="r" start="0x1FFF7800" size="0x200"/>
+                <device Dname="Device"/>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        let memories = &devices.0["Device"].memories;
+        let names = |regions: Vec<&Memory>| -> Vec<String> {
+            regions.into_iter().map(|m| m.name.clone()).collect()
+        };
+        assert_eq!(names(memories.writable()), vec!["ITCM", "SRAM", "BKPSRAM"]);
+        assert_eq!(names(memories.executable()), vec!["ITCM", "FLASH", "SRAM"]);
+        assert!(memories.executable().iter().all(|m| m.is_executable()));
+        assert_eq!(names(memories.peripherals()), vec!["PERIPH"]);
+        assert_eq!(
+            names(memories.by_access(|access| access.read && !access.write)),
+            vec!["FLASH", "OTP"]
+        );
+    }
+
+    #[test]
+    fn peripheral_regions_excluded() {
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="Family">
+                <processor Dcore="Cortex-M4"/>
+                <memory name="FLASH_REGS" access="rxp" start="0x00000000" size="0x1000" startup="1"/>
+                <memory name="FLASH" access="rx" start="0x08000000" size="0x100000"/>
+                <memory name="REGS" access="rwp" start="0x10000000" size="0x1000" default="1"/>
+                <memory name="SRAM" access="rw" start="0x20000000" size="0x20000"/>
+                <device Dname="Device"/>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        let device = &devices.0["Device"];
+        let names = |regions: Vec<&Memory>| -> Vec<String> {
+            regions.into_iter().map(|m| m.name.clone()).collect()
+        };
+        assert_eq!(device.total_flash(), 0x10_0000);
+        assert_eq!(device.total_ram(), 0x2_0000);
+        assert_eq!(device.default_rom().unwrap().name, "FLASH");
+        assert_eq!(device.default_ram().unwrap().name, "SRAM");
+        assert_eq!(names(device.memory_map()), vec!["FLASH", "SRAM"]);
+        assert_eq!(
+            names(device.memories.non_peripheral()),
+            vec!["FLASH", "SRAM"]
+        );
+    }
+
+    #[test]
+    fn access_from_memory_type() {
+        let access = |xml: &str| MemElem::from_string(xml).unwrap().1.access;
+        assert_eq!(
+            access(r#"<memory name="SDRAM" type="RAM" start="0xC0000000" size="0x800000"/>"#),
+            MemoryPermissions::from_str("rw")
+        );
+        assert_eq!(
+            access(r#"<memory name="BOOT" type="rom" start="0x0" size="0x1000"/>"#),
+            MemoryPermissions::from_str("rx")
+        );
+        // The type wins over an id that says otherwise, and `access` wins over both.
+        assert_eq!(
+            access(r#"<memory id="IROM1" type="RAM" start="0x0" size="0x1000"/>"#),
+            MemoryPermissions::from_str("rw")
+        );
+        assert_eq!(
+            access(r#"<memory name="SRAM" type="RAM" access="rwx" start="0x0" size="0x1000"/>"#),
+            MemoryPermissions::from_str("rwx")
+        );
+        assert_eq!(
+            access(r#"<memory id="IRAM1" type="other" start="0x0" size="0x1000"/>"#),
+            MemoryPermissions::from_str("rw")
+        );
+    }
+
+    #[test]
+    fn merge_adjacent_memories() {
+        let mem = |name: &str, access: &str, start, size, startup| Memory {
+            name: name.to_string(),
+            p_name: None,
+            p_unit: None,
+            condition: None,
+            access: MemoryPermissions::from_str(access),
+            start,
+            size,
+            startup,
+            default: true,
+            extra_attributes: HashMap::new(),
+        };
+        let memories: Memories = [
+            mem("FLASH_B", "rx", 0x0808_0000, 0x8_0000, false),
+            mem("FLASH_A", "rx", 0x0800_0000, 0x8_0000, true),
+            mem("OTP", "r", 0x0810_0000, 0x200, false),
+            mem("SRAM1", "rw", 0x2000_0000, 0x1_0000, false),
+            mem("SRAM3", "rw", 0x2002_0000, 0x1_0000, false),
+        ]
+        .into_iter()
+        .map(|mem| (mem.name.clone(), mem))
+        .collect();
+        let merged = memories.merge_adjacent();
+        assert_eq!(merged.0.len(), 4);
+        let flash = &merged.0["FLASH_A"];
+        assert_eq!(flash.start, 0x0800_0000);
+        assert_eq!(flash.size, 0x10_0000);
+        assert!(flash.startup);
+        assert!(merged.0.contains_key("OTP"));
+        assert!(merged.0.contains_key("SRAM1"));
+        assert!(merged.0.contains_key("SRAM3"));
+        assert_eq!(memories.0.len(), 5);
+    }
+
+    #[test]
+    fn memories_iterate_and_extend() {
+        let mem = |name: &str, start| Memory {
+            name: name.to_string(),
+            p_name: None,
+            p_unit: None,
+            condition: None,
+            access: MemoryPermissions::from_str("rw"),
+            start,
+            size: 0x1000,
+            startup: false,
+            default: false,
+            extra_attributes: HashMap::new(),
+        };
+        let mut memories: Memories = [mem("A", 0x1000), mem("B", 0x2000)]
+            .into_iter()
+            .map(|m| (m.name.clone(), m))
+            .collect();
+        memories.extend([
+            ("B".to_string(), mem("B", 0x3000)),
+            ("C".to_string(), mem("C", 0x4000)),
+        ]);
+
+        let mut starts: Vec<(&str, u64)> = (&memories)
+            .into_iter()
+            .map(|(name, m)| (name.as_str(), m.start))
+            .collect();
+        starts.sort();
+        assert_eq!(starts, [("A", 0x1000), ("B", 0x3000), ("C", 0x4000)]);
+
+        let mut names: Vec<String> = memories.into_iter().map(|(name, _)| name).collect();
+        names.sort();
+        assert_eq!(names, ["A", "B", "C"]);
+    }
+
+    #[test]
+    fn memory_display() {
+        let mem = Memory {
+            name: "IROM1".into(),
+            p_name: None,
+            p_unit: None,
+            condition: None,
+            access: MemoryPermissions::from_str("rx"),
+            start: 0x0800_0000,
+            size: 0x10_0000,
+            startup: true,
+            default: true,
+            extra_attributes: HashMap::new(),
+        };
+        assert_eq!(mem.size_human(), "1 MB");
+        assert_eq!(mem.to_string(), "0x08000000..0x08100000 (1 MB, rx)");
+    }
+
+    #[test]
+    fn well_known_memory_ids() {
+        let mem = |name: &str| Memory {
+            name: name.into(),
+            p_name: None,
+            p_unit: None,
+            condition: None,
+            access: MemoryPermissions::from_str("rx"),
+            start: 0,
+            size: 0x1000,
+            startup: false,
+            default: false,
+            extra_attributes: HashMap::new(),
+        };
+        for (name, expected) in [
+            ("IROM1", Some(WellKnownMemory::Irom1)),
+            ("IROM2", Some(WellKnownMemory::Irom2)),
+            ("IRAM1", Some(WellKnownMemory::Iram1)),
+            ("IRAM2", Some(WellKnownMemory::Iram2)),
+            ("FLASH", Some(WellKnownMemory::ProgramFlash)),
+            ("PROGRAM_FLASH", Some(WellKnownMemory::ProgramFlash)),
+            ("PFLASH", Some(WellKnownMemory::ProgramFlash)),
+            ("CODE_FLASH", Some(WellKnownMemory::ProgramFlash)),
+            ("Flash", Some(WellKnownMemory::ProgramFlash)),
+            ("DATA_FLASH", Some(WellKnownMemory::DataFlash)),
+            ("DFLASH", Some(WellKnownMemory::DataFlash)),
+            ("SRAM", Some(WellKnownMemory::Sram)),
+            ("RAM", Some(WellKnownMemory::Sram)),
+            ("EEPROM", Some(WellKnownMemory::Eeprom)),
+            ("OTP", Some(WellKnownMemory::Otp)),
+            ("SRAM1", None),
+            ("QSPI", None),
+        ] {
+            assert_eq!(mem(name).well_known(), expected, "{}", name);
+        }
+        assert!(WellKnownMemory::ProgramFlash.is_main_flash());
+        assert!(WellKnownMemory::Irom1.is_main_flash());
+        assert!(!WellKnownMemory::DataFlash.is_main_flash());
+        assert!(WellKnownMemory::Sram.is_main_ram());
+        assert!(!WellKnownMemory::Iram2.is_main_ram());
+    }
+
+    #[test]
+    fn memory_end_is_exclusive() {
+        let mut mem = Memory {
+            name: "TOP".into(),
+            p_name: None,
+            p_unit: None,
+            condition: None,
+            access: MemoryPermissions::from_str("rw"),
+            start: u64::MAX - 0x1F,
+            size: 0x10,
+            startup: false,
+            default: false,
+            extra_attributes: HashMap::new(),
+        };
+        assert_eq!(mem.end(), u64::MAX - 0xF);
+        mem.size = 0x1F;
+        assert_eq!(mem.end(), u64::MAX);
+        mem.size = 0x20;
+        assert_eq!(mem.end(), u64::MAX);
+
+        let json = serde_json::to_value(&mem).unwrap();
+        assert_eq!(json["end"], u64::MAX);
+        let back: Memory = serde_json::from_value(json).unwrap();
+        assert_eq!(back.start, mem.start);
+        assert_eq!(back.size, mem.size);
+    }
+
+    #[test]
+    fn device_summary() {
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="Family" Dvendor="Vendor:1">
+                <processor Dcore="Cortex-M4"/>
+                <memory id="IROM1" start="0x08000000" size="0x100000" startup="1" default="1"/>
+                <memory id="IRAM1" start="0x20000000" size="0x20000" default="1"/>
+                <memory id="IRAM2" start="0x10000000" size="0x10000"/>
+                <device Dname="Device"/>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        let device = &devices.0["Device"];
+        assert_eq!(device.total_flash(), 0x10_0000);
+        assert_eq!(device.total_ram(), 0x3_0000);
+        assert_eq!(
+            device.summary(),
+            "Device: Cortex-M4, 1 MB flash, 192 KB RAM"
+        );
+    }
+
+    #[test]
+    fn access_port_as_u64() {
+        assert_eq!(AccessPort::Index(2).as_u64(), 2);
+        assert!(!AccessPort::Index(2).is_address());
+        assert_eq!(AccessPort::Address(0x8000_2000).as_u64(), 0x8000_2000);
+        assert!(AccessPort::Address(0x8000_2000).is_address());
+    }
+
+    #[test]
+    fn debug_without_pname_matches_all() {
+        let d = debug(None, None);
+        assert!(processor(None, 0).matches_debug(&d));
+        assert!(processor(Some("cm4"), 0).matches_debug(&d));
+        assert!(processor(Some("cm7"), 1).matches_debug(&d));
+    }
+
+    #[test]
+    fn debug_with_pname_matches_only_that_processor() {
+        let d = debug(Some("cm4"), None);
+        assert!(processor(Some("cm4"), 0).matches_debug(&d));
+        assert!(processor(Some("cm4"), 1).matches_debug(&d));
+        assert!(!processor(Some("cm7"), 0).matches_debug(&d));
+        assert!(!processor(None, 0).matches_debug(&d));
+
+        let d = debug(Some("cm4"), Some(1));
+        assert!(!processor(Some("cm4"), 0).matches_debug(&d));
+        assert!(processor(Some("cm4"), 1).matches_debug(&d));
+    }
+
+    #[test]
+    fn memories_scoped_by_unit() {
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="Family">
+                <processor Pname="cm4" Dcore="Cortex-M4" Punits="2"/>
+                <memory id="FLASH" start="0x08000000" size="0x100000" startup="1"/>
+                <memory id="RAM0" Pname="cm4" Punit="0" access="rw" start="0x20000000" size="0x10000"/>
+                <memory id="RAM1" Pname="cm4" Punit="1" access="rw" start="0x20010000" size="0x10000"/>
+                <device Dname="Device"/>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        let device = &devices.0["Device"];
+        assert_eq!(device.memories.0["RAM1"].p_unit, Some(1));
+        assert_eq!(device.memories.0["FLASH"].p_unit, None);
+        let names = |unit: usize| -> Vec<&str> {
+            let processor = device.processors.iter().find(|p| p.unit == unit).unwrap();
+            device
+                .memories_for_processor(processor)
+                .into_iter()
+                .map(|m| m.name.as_str())
+                .collect()
+        };
+        assert_eq!(names(0), ["FLASH", "RAM0"]);
+        assert_eq!(names(1), ["FLASH", "RAM1"]);
+    }
+
+    #[test]
+    fn processor_indices() {
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="Family">
+                <processor Pname="cm7" Dcore="Cortex-M7"/>
+                <processor Pname="cm4" Dcore="Cortex-M4" Punits="2"/>
+                <device Dname="Device"/>
+              </family>
+              <family Dfamily="Single">
+                <processor Dcore="Cortex-M0"/>
+                <device Dname="Single"/>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        let device = &devices.0["Device"];
+        let indices: Vec<Option<usize>> = device
+            .processors
+            .iter()
+            .map(|p| device.processor_index(p))
+            .collect();
+        assert_eq!(indices, [Some(0), Some(1), Some(2)]);
+        let mut unit1 = device
+            .processors
+            .iter()
+            .find(|p| p.name.as_deref() == Some("cm4") && p.unit == 1)
+            .unwrap()
+            .clone();
+        unit1.svd = Some("Changed.svd".into());
+        assert_eq!(
+            device.processors[device.processor_index(&unit1).unwrap()].unit,
+            1
+        );
+        unit1.unit = 2;
+        assert_eq!(device.processor_index(&unit1), None);
+
+        let single = &devices.0["Single"];
+        assert_eq!(single.processor_index(&single.processors[0]), Some(0));
+        assert_eq!(single.processor_index(&device.processors[0]), None);
+    }
+
+    #[test]
+    fn debug_pname_ignores_case() {
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="Family">
+                <processor Pname="cm7" Dcore="Cortex-M7"/>
+                <processor Pname="cm4" Dcore="Cortex-M4"/>
+                <debug Pname="CM7" __dp="0" __ap="0" svd="CM7.svd"/>
+                <debug Pname="CM4" __dp="1" __ap="3" svd="CM4.svd"/>
+                <memory id="FLASH" Pname="cm7" access="rx" start="0x08000000" size="0x100000" startup="1"/>
+                <memory id="CM4_BOOT" Pname="CM4" access="rx" start="0x08100000" size="0x1000" startup="1"/>
+                <device Dname="Device"/>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        let processors = &devices.0["Device"].processors;
+        let cm4 = processors
+            .iter()
+            .find(|p| p.name.as_deref() == Some("cm4"))
+            .unwrap();
+        assert_eq!(cm4.svd.as_deref(), Some("CM4.svd"));
+        assert_eq!(cm4.dp, 1);
+        assert_eq!(cm4.ap, AccessPort::Index(3));
+        let cm7 = processors
+            .iter()
+            .find(|p| p.name.as_deref() == Some("cm7"))
+            .unwrap();
+        assert_eq!(cm7.svd.as_deref(), Some("CM7.svd"));
+        assert!(cm7.matches_debug(&debug(Some("CM7"), None)));
+
+        // Memory scoped to `CM4` belongs to `cm4` too.
+        let device = &devices.0["Device"];
+        assert!(device.memories.0["CM4_BOOT"].applies_to_processor(cm4));
+        assert!(!device.memories.0["CM4_BOOT"].applies_to_processor(cm7));
+        assert_eq!(device.startup_memory(cm4).unwrap().name, "CM4_BOOT");
+        assert_eq!(device.validate(), vec![]);
+    }
+
+    #[test]
+    fn multidrop_targetsel() {
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="Family">
+                <processor Pname="core0" Dcore="Cortex-M33"/>
+                <processor Pname="core1" Dcore="Cortex-M33"/>
+                <device Dname="OnAccessPort">
+                  <accessportV2 __apid="0" __dp="0" address="0x2000" __targetsel="0x01002927"/>
+                  <accessportV1 __apid="1" __dp="1" index="0"/>
+                  <debug Pname="core0" __apid="0"/>
+                  <debug Pname="core1" __apid="1" __targetsel="0x11002927"/>
+                </device>
+                <device Dname="OnDebug">
+                  <debug Pname="core0" __dp="0" __ap="0" __targetsel="0x01002927"/>
+                  <debug Pname="core1" __dp="1" __ap="0" __targetsel="0x100000000"/>
+                </device>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        let targetsels = |name: &str| -> Vec<Option<u32>> {
+            devices.0[name]
+                .processors
+                .iter()
+                .map(|p| p.targetsel)
+                .collect()
+        };
+        assert_eq!(
+            targetsels("OnAccessPort"),
+            [Some(0x0100_2927), Some(0x1100_2927)]
+        );
+        assert_eq!(targetsels("OnDebug"), [Some(0x0100_2927), None]);
+    }
+
+    #[test]
+    fn skipped_elements_are_reported() {
+        let xml = r#"<devices>
+          <family Dfamily="Family">
+            <processor Dcore="Cortex-M4"/>
+            <memory id="IROM1" access="rx" size="0x1000"/>
+            <device Dname="Device">
+              <algorithm name="Flash/Missing.FLM" start="0x08000000"/>
+            </device>
+          </family>
+        </devices>"#;
+        let mut diags: Vec<Diagnostic> = Vec::new();
+        let devices =
+            Devices::from_string_with_diagnostics(xml, &ParseOptions::default(), &mut diags)
+                .unwrap();
+        assert!(devices.0["Device"].algorithms.is_empty());
+        assert_eq!(diags.len(), 2);
+        assert_eq!(diags[0].tag, "memory");
+        assert_eq!(diags[0].message, "start not found in memory element");
+        assert_eq!(
+            &xml[diags[0].range.clone()],
+            r#"<memory id="IROM1" access="rx" size="0x1000"/>"#
+        );
+        assert_eq!(diags[1].tag, "algorithm");
+        assert_eq!(diags[1].message, "size not found in algorithm element");
+        assert_eq!(
+            &xml[diags[1].range.clone()],
+            r#"<algorithm name="Flash/Missing.FLM" start="0x08000000"/>"#
+        );
+    }
+
+    #[test]
+    fn dropped_elements_are_reported() {
+        let xml = r#"<devices>
+          <family Dfamily="Family">
+            <processor Dcore="Cortex-M4"/>
+            <debug address="nowhere" __targetsel="0x100000000"/>
+            <device Dname="Device">
+              <algorithm remove="1"/>
+              <variant/>
+              <variant Dvariant="Variant"/>
+            </device>
+            <device Dname="Variant"/>
+          </family>
+        </devices>"#;
+        let mut diags: Vec<Diagnostic> = Vec::new();
+        let devices =
+            Devices::from_string_with_diagnostics(xml, &ParseOptions::default(), &mut diags)
+                .unwrap();
+        assert_eq!(devices.0.len(), 1);
+        let reported: Vec<(&str, &str)> = diags
+            .iter()
+            .map(|d| (d.tag.as_str(), &xml[d.range.clone()]))
+            .collect();
+        assert_eq!(
+            reported,
+            vec![
+                (
+                    "debug",
+                    r#"<debug address="nowhere" __targetsel="0x100000000"/>"#
+                ),
+                (
+                    "debug",
+                    r#"<debug address="nowhere" __targetsel="0x100000000"/>"#
+                ),
+                ("algorithm", r#"<algorithm remove="1"/>"#),
+                ("variant", "<variant/>"),
+                ("variant", r#"<variant Dvariant="Variant"/>"#),
+            ]
+        );
+        assert_eq!(
+            diags[3].message,
+            "Variant of Device found without a Dvariant"
+        );
+        assert_eq!(
+            diags[4].to_string(),
+            format!(
+                "variant at bytes {}..{}: Duplicate device Variant, replaced by a later declaration",
+                diags[4].range.start, diags[4].range.end
+            )
+        );
+    }
+
+    #[test]
+    fn inherited_permissions_are_combined() {
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="Family">
+                <processor Dcore="Cortex-M4"/>
+                <memory id="IRAM1" access="rw" start="0x20000000" size="0x10000" default="1"/>
+                <device Dname="Device">
+                  <memory id="IRAM1" access="rx" start="0x20000000" size="0x8000"/>
+                </device>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        let iram = &devices.0["Device"].memories.0["IRAM1"];
+        assert_eq!(iram.access, MemoryPermissions::from_str("rwx"));
+        assert_eq!(iram.size, 0x8000);
+        assert!(!iram.default);
+        assert_eq!(
+            MemoryPermissions::from_str("rs").union(&MemoryPermissions::from_str("wn")),
+            MemoryPermissions::from_str("rwsn")
+        );
+    }
+
+    #[test]
+    fn algorithm_paths_stay_in_the_pack() {
+        let resolve = |name: &str| {
+            let opts = ParseOptions {
+                path_style: PathStyle::Raw,
+                ..Default::default()
+            };
+            let xml = format!(r#"<algorithm name="{}" start="0x0" size="0x1000"/>"#, name);
+            Algorithm::from_string_with(&xml, &opts)
+                .unwrap()
+                .resolve_path(Path::new("pack"))
+        };
+        let expected = Path::new("pack").join("Flash").join("alg.FLM");
+        assert_eq!(resolve("Flash/alg.FLM").unwrap(), expected);
+        assert_eq!(resolve(r".\Flash/Sub\..\alg.FLM").unwrap(), expected);
+        assert!(resolve(r"..\escape.FLM").is_err());
+        assert!(resolve("Flash/../../escape.FLM").is_err());
+        assert!(resolve("/etc/escape.FLM").is_err());
+        assert!(resolve("Flash/..").is_err());
     }
 }
@@ -0,0 +1,262 @@
+use std::cmp::Ordering;
+
+/// Best-effort decomposition of device names, for grouping and natural sorting.
+///
+/// Device names are not structured by the PDSC format; each vendor has its own ordering scheme.
+/// [`DeviceName::parse`] recognises a few common schemes (STM32, Nordic nRF, Microchip SAM) and
+/// otherwise only splits off the leading series.
+pub struct DeviceName;
+
+/// The parts of a device name, as decoded by [`DeviceName::parse`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DeviceNameParts {
+    /// The product series, e.g. `STM32F4` in `STM32F407VGTx`.
+    pub series: String,
+    /// The product line within the series, e.g. `07` in `STM32F407VGTx`.
+    pub line: Option<String>,
+    /// The pin count code, e.g. `V` in `STM32F407VGTx`.
+    pub pin_count: Option<String>,
+    /// The memory size code, e.g. `G` in `STM32F407VGTx` or `AA` in `nRF52840_xxAA`.
+    pub size_code: Option<String>,
+    /// The package code, e.g. `T` in `STM32F407VGTx`.
+    pub package: Option<String>,
+    /// The temperature grade, e.g. `6` in `STM32F407VGT6`.
+    pub temp_grade: Option<String>,
+    /// The name without its package and temperature grade, e.g. `STM32F407VG`.
+    pub base: String,
+    /// The name split into runs of letters and of digits, e.g. `["STM", "32", "F", "407", ...]`.
+    pub tokens: Vec<String>,
+}
+
+impl DeviceName {
+    /// Decomposes a device name into its parts.
+    pub fn parse(name: &str) -> DeviceNameParts {
+        let tokens = tokens(name);
+        parse_stm32(name)
+            .or_else(|| parse_nrf(name))
+            .or_else(|| parse_sam(name))
+            .unwrap_or_else(|| DeviceNameParts {
+                series: generic_series(name).to_string(),
+                base: name.to_string(),
+                ..Default::default()
+            })
+            .with_tokens(tokens)
+    }
+
+    /// Compares device names so that embedded numbers sort numerically, e.g. `LPC55S6` before
+    /// `LPC55S16`.
+    ///
+    /// Letters compare case-insensitively, and numbers sort before letters.
+    pub fn natural_cmp(lhs: &str, rhs: &str) -> Ordering {
+        let lhs = tokens(lhs);
+        let rhs = tokens(rhs);
+        for (l, r) in lhs.iter().zip(rhs.iter()) {
+            let ordering = match (l.parse::<u64>(), r.parse::<u64>()) {
+                (Ok(l), Ok(r)) => l.cmp(&r),
+                (Ok(_), Err(_)) => Ordering::Less,
+                (Err(_), Ok(_)) => Ordering::Greater,
+                (Err(_), Err(_)) => l.to_ascii_lowercase().cmp(&r.to_ascii_lowercase()),
+            };
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+        }
+        lhs.len().cmp(&rhs.len())
+    }
+}
+
+impl DeviceNameParts {
+    fn with_tokens(self, tokens: Vec<String>) -> Self {
+        Self { tokens, ..self }
+    }
+}
+
+fn tokens(name: &str) -> Vec<String> {
+    let mut tokens: Vec<String> = Vec::new();
+    let mut last_digit = None;
+    for c in name.chars() {
+        if !c.is_ascii_alphanumeric() {
+            last_digit = None;
+            continue;
+        }
+        let digit = c.is_ascii_digit();
+        match tokens.last_mut() {
+            Some(token) if last_digit == Some(digit) => token.push(c),
+            _ => tokens.push(c.to_string()),
+        }
+        last_digit = Some(digit);
+    }
+    tokens
+}
+
+/// The leading letters and first run of digits, e.g. `LPC55` for `LPC55S69JBD100`.
+fn generic_series(name: &str) -> &str {
+    let letters = name
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(name.len());
+    let digits = name[letters..]
+        .find(|c: char| !c.is_ascii_digit())
+        .map_or(name.len(), |i| letters + i);
+    &name[..digits]
+}
+
+fn optional(s: &str, range: std::ops::Range<usize>) -> Option<String> {
+    s.get(range).filter(|s| !s.is_empty()).map(String::from)
+}
+
+/// `STM32` <type> [<core>] <line> <pins> <flash> <package> <temperature>, e.g. `STM32F407VGTx`,
+/// `STM32L4R5ZITx` or `STM32WB55RGVx`.
+fn parse_stm32(name: &str) -> Option<DeviceNameParts> {
+    let rest = name.strip_prefix("STM32")?;
+    let kind = rest.find(|c: char| !c.is_ascii_uppercase())?;
+    if kind == 0 || kind > 2 {
+        return None;
+    }
+    // Single letter types (F, L, G, H, U, C) are followed by a core digit.
+    let series_len = if kind == 1 { 2 } else { kind };
+    let series = rest.get(..series_len)?;
+    let rest = &rest[series_len..];
+    if rest.len() < 2 || !rest.is_ascii() {
+        return None;
+    }
+    let base_len = 5 + series_len + rest.len().min(4);
+    Some(DeviceNameParts {
+        series: format!("STM32{}", series),
+        line: optional(rest, 0..2),
+        pin_count: optional(rest, 2..3),
+        size_code: optional(rest, 3..4),
+        package: optional(rest, 4..5),
+        temp_grade: optional(rest, 5..6),
+        base: name[..base_len].to_string(),
+        tokens: Vec::new(),
+    })
+}
+
+/// `nRF` <series> <line> [`_xx` <variant>], e.g. `nRF52840_xxAA`.
+fn parse_nrf(name: &str) -> Option<DeviceNameParts> {
+    let rest = name.strip_prefix("nRF")?;
+    let digits = rest
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(rest.len());
+    if digits < 3 {
+        return None;
+    }
+    let size_code = rest[digits..]
+        .strip_prefix("_xx")
+        .filter(|code| !code.is_empty())
+        .map(String::from);
+    Some(DeviceNameParts {
+        series: format!("nRF{}", &rest[..2]),
+        line: Some(rest[2..digits].to_string()),
+        size_code,
+        base: name.to_string(),
+        ..Default::default()
+    })
+}
+
+/// `ATSAM` <series> <pins> <flash> [<revision>] [`-` <package and temperature>], e.g.
+/// `ATSAMD21G18A-AU`.
+fn parse_sam(name: &str) -> Option<DeviceNameParts> {
+    let rest = name.strip_prefix("ATSAM")?;
+    let (part, package) = match rest.split_once('-') {
+        Some((part, package)) => (part, Some(package.to_string())),
+        None => (rest, None),
+    };
+    let bytes = part.as_bytes();
+    if bytes.len() < 6
+        || !bytes[0].is_ascii_uppercase()
+        || !bytes[1..3].iter().all(u8::is_ascii_digit)
+        || !bytes[3].is_ascii_uppercase()
+        || !bytes[4..6].iter().all(u8::is_ascii_digit)
+    {
+        return None;
+    }
+    Some(DeviceNameParts {
+        series: format!("ATSAM{}", &part[..3]),
+        pin_count: optional(part, 3..4),
+        size_code: optional(part, 4..6),
+        package,
+        base: format!("ATSAM{}", part),
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn stm32_names() {
+        let parts = DeviceName::parse("STM32F407VGTx");
+        assert_eq!(parts.series, "STM32F4");
+        assert_eq!(parts.line.as_deref(), Some("07"));
+        assert_eq!(parts.pin_count.as_deref(), Some("V"));
+        assert_eq!(parts.size_code.as_deref(), Some("G"));
+        assert_eq!(parts.package.as_deref(), Some("T"));
+        assert_eq!(parts.temp_grade.as_deref(), Some("x"));
+        assert_eq!(parts.base, "STM32F407VG");
+
+        let parts = DeviceName::parse("STM32L4R5ZITx");
+        assert_eq!(parts.series, "STM32L4");
+        assert_eq!(parts.line.as_deref(), Some("R5"));
+        assert_eq!(parts.size_code.as_deref(), Some("I"));
+
+        let parts = DeviceName::parse("STM32WB55RGVx");
+        assert_eq!(parts.series, "STM32WB");
+        assert_eq!(parts.line.as_deref(), Some("55"));
+        assert_eq!(parts.package.as_deref(), Some("V"));
+
+        let parts = DeviceName::parse("STM32F407");
+        assert_eq!(parts.series, "STM32F4");
+        assert_eq!(parts.line.as_deref(), Some("07"));
+        assert_eq!(parts.pin_count, None);
+        assert_eq!(parts.package, None);
+        assert_eq!(parts.base, "STM32F407");
+    }
+
+    #[test]
+    fn nrf_names() {
+        let parts = DeviceName::parse("nRF52840_xxAA");
+        assert_eq!(parts.series, "nRF52");
+        assert_eq!(parts.line.as_deref(), Some("840"));
+        assert_eq!(parts.size_code.as_deref(), Some("AA"));
+
+        let parts = DeviceName::parse("nRF5340_xxAA");
+        assert_eq!(parts.series, "nRF53");
+        assert_eq!(parts.line.as_deref(), Some("40"));
+    }
+
+    #[test]
+    fn sam_names() {
+        let parts = DeviceName::parse("ATSAMD21G18A-AU");
+        assert_eq!(parts.series, "ATSAMD21");
+        assert_eq!(parts.pin_count.as_deref(), Some("G"));
+        assert_eq!(parts.size_code.as_deref(), Some("18"));
+        assert_eq!(parts.package.as_deref(), Some("AU"));
+        assert_eq!(parts.base, "ATSAMD21G18A");
+    }
+
+    #[test]
+    fn other_names() {
+        let parts = DeviceName::parse("LPC55S69JBD100");
+        assert_eq!(parts.series, "LPC55");
+        assert_eq!(parts.base, "LPC55S69JBD100");
+        assert_eq!(parts.tokens, vec!["LPC", "55", "S", "69", "JBD", "100"]);
+        assert_eq!(parts.package, None);
+
+        assert_eq!(DeviceName::parse("STM32").series, "STM32");
+        assert_eq!(DeviceName::parse("").series, "");
+    }
+
+    #[test]
+    fn natural_order() {
+        let mut names = vec![
+            "LPC55S69", "LPC55S16", "LPC55S6", "nrf52840", "nRF52832", "nRF5340",
+        ];
+        names.sort_by(|a, b| DeviceName::natural_cmp(a, b));
+        assert_eq!(
+            names,
+            vec!["LPC55S6", "LPC55S16", "LPC55S69", "nRF5340", "nRF52832", "nrf52840"]
+        );
+    }
+}
@@ -0,0 +1,162 @@
+use super::device::strip_vendor_id;
+use super::{Core, Device, Devices, TrustZone};
+
+/// A query over [`Devices`], see [`Devices::find`]. A device must meet every criterion set; a
+/// filter without any matches every device.
+#[derive(Debug, Clone, Default)]
+pub struct DeviceFilter {
+    core: Option<Core>,
+    vendor: Option<String>,
+    min_flash: Option<u64>,
+    trustzone: Option<bool>,
+}
+
+impl DeviceFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Devices with a processor of `core`, as given by [`Device::cores`] and compared with
+    /// [`Core::matches`].
+    pub fn core(mut self, core: Core) -> Self {
+        self.core = Some(core);
+        self
+    }
+
+    /// Devices of `vendor`, compared by [`Device::vendor_name`]. A `:id` suffix on `vendor` is
+    /// ignored.
+    pub fn vendor(mut self, vendor: &str) -> Self {
+        self.vendor = Some(strip_vendor_id(vendor).to_string());
+        self
+    }
+
+    /// Devices with at least `bytes` of flash, counted as the total size of the regions that
+    /// are both readable and executable.
+    ///
+    /// Unlike [`Device::total_flash`], this goes by access alone, so executable RAM counts too.
+    pub fn min_flash(mut self, bytes: u64) -> Self {
+        self.min_flash = Some(bytes);
+        self
+    }
+
+    /// Devices with, or without, a processor implementing TrustZone.
+    pub fn has_trustzone(mut self, trustzone: bool) -> Self {
+        self.trustzone = Some(trustzone);
+        self
+    }
+
+    /// Returns true if `device` meets every criterion of this filter.
+    pub fn matches(&self, device: &Device) -> bool {
+        if let Some(core) = &self.core {
+            if !device.cores().iter().any(|c| c.matches(core)) {
+                return false;
+            }
+        }
+        if let Some(vendor) = &self.vendor {
+            if device.vendor_name() != Some(vendor.as_str()) {
+                return false;
+            }
+        }
+        if let Some(min_flash) = self.min_flash {
+            let flash = device
+                .memories
+                .by_access(|access| access.read && access.execute)
+                .iter()
+                .map(|m| m.size)
+                .fold(0, u64::saturating_add);
+            if flash < min_flash {
+                return false;
+            }
+        }
+        if let Some(trustzone) = self.trustzone {
+            let has = device.processors.iter().any(|p| p.tz == TrustZone::Present);
+            if has != trustzone {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+impl Devices {
+    /// The devices matching `filter`, sorted by name.
+    pub fn find(&self, filter: &DeviceFilter) -> Vec<&Device> {
+        let mut found: Vec<&Device> = self.0.values().filter(|d| filter.matches(d)).collect();
+        found.sort_by(|a, b| a.name.cmp(&b.name));
+        found
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::prelude::*;
+
+    const DEVICES: &str = r#"<devices>
+      <family Dfamily="STM32F4 Series" Dvendor="STMicroelectronics:13">
+        <processor Dcore="Cortex-M4" Dfpu="SP_FPU"/>
+        <memory id="IRAM1" access="rw" start="0x20000000" size="0x20000"/>
+        <device Dname="STM32F401CB">
+          <memory id="IROM1" access="rx" start="0x08000000" size="0x20000" startup="1"/>
+        </device>
+        <device Dname="STM32F407VG">
+          <memory id="IROM1" access="rx" start="0x08000000" size="0x100000" startup="1"/>
+        </device>
+      </family>
+      <family Dfamily="LPC55S6x" Dvendor="NXP:11">
+        <processor Dcore="Cortex-M33" Dtz="TZ"/>
+        <device Dname="LPC55S69">
+          <memory id="FLASH" access="rx" start="0x00000000" size="0x98000" startup="1"/>
+          <memory id="SRAM" access="rwx" start="0x20000000" size="0x44000"/>
+        </device>
+      </family>
+    </devices>"#;
+
+    fn names(devices: Vec<&Device>) -> Vec<&str> {
+        devices.into_iter().map(|d| d.name.as_str()).collect()
+    }
+
+    #[test]
+    fn find_devices() {
+        let devices = Devices::from_string(DEVICES).unwrap();
+        assert_eq!(
+            names(devices.find(&DeviceFilter::new())),
+            ["LPC55S69", "STM32F401CB", "STM32F407VG"]
+        );
+        let filter = DeviceFilter::new()
+            .core(Core::CortexM4)
+            .vendor("STMicroelectronics")
+            .min_flash(256 * 1024);
+        assert_eq!(names(devices.find(&filter)), ["STM32F407VG"]);
+        assert_eq!(
+            names(devices.find(&DeviceFilter::new().vendor("STMicroelectronics:13"))),
+            ["STM32F401CB", "STM32F407VG"]
+        );
+        assert_eq!(
+            names(devices.find(&DeviceFilter::new().has_trustzone(true))),
+            ["LPC55S69"]
+        );
+        assert_eq!(
+            names(devices.find(&DeviceFilter::new().has_trustzone(false).core(Core::Any))),
+            ["STM32F401CB", "STM32F407VG"]
+        );
+        assert!(devices
+            .find(
+                &DeviceFilter::new()
+                    .core(Core::CortexM33)
+                    .min_flash(1024 * 1024)
+            )
+            .is_empty());
+    }
+
+    #[test]
+    fn min_flash_counts_executable_ram() {
+        let devices = Devices::from_string(DEVICES).unwrap();
+        let lpc = &devices.0["LPC55S69"];
+        assert_eq!(lpc.total_flash(), 0x9_8000);
+        let filter = DeviceFilter::new()
+            .vendor("NXP")
+            .min_flash(0x9_8000 + 0x4_4000);
+        assert_eq!(names(devices.find(&filter)), ["LPC55S69"]);
+    }
+}
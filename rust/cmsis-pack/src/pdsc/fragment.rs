@@ -0,0 +1,351 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use super::{
+    AccessPort, AlgorithmStyle, Device, Endian, Memory, MemoryPermissions, TrustZone, FPU, MPU,
+};
+
+/// Escapes `value` for use in an attribute value or element text.
+fn escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn attr(out: &mut String, name: &str, value: &str) {
+    let _ = write!(out, " {}=\"{}\"", name, escape(value));
+}
+
+fn hex_attr(out: &mut String, name: &str, value: u64) {
+    let _ = write!(out, " {}=\"0x{:08X}\"", name, value);
+}
+
+/// Appends `extra` in name order, so the output doesn't depend on map iteration order.
+fn extra_attrs(out: &mut String, extra: &HashMap<String, String>) {
+    let mut extra: Vec<_> = extra.iter().collect();
+    extra.sort();
+    for (name, value) in extra {
+        attr(out, name, value);
+    }
+}
+
+/// The `access` value for `access`, with the permissions in their conventional order.
+fn access(access: &MemoryPermissions) -> String {
+    [
+        (access.read, 'r'),
+        (access.write, 'w'),
+        (access.execute, 'x'),
+        (access.peripheral, 'p'),
+        (access.secure, 's'),
+        (access.non_secure, 'n'),
+        (access.non_secure_callable, 'c'),
+    ]
+    .iter()
+    .filter(|(set, _)| *set)
+    .map(|(_, c)| c)
+    .collect()
+}
+
+fn memory(out: &mut String, memory: &Memory) {
+    out.push_str("  <memory");
+    attr(out, "name", &memory.name);
+    if let Some(p_name) = &memory.p_name {
+        attr(out, "Pname", p_name);
+    }
+    if let Some(unit) = memory.p_unit {
+        attr(out, "Punit", &unit.to_string());
+    }
+    if let Some(condition) = &memory.condition {
+        attr(out, "condition", condition);
+    }
+    attr(out, "access", &access(&memory.access));
+    hex_attr(out, "start", memory.start);
+    hex_attr(out, "size", memory.size);
+    if memory.startup {
+        attr(out, "startup", "1");
+    }
+    if memory.default {
+        attr(out, "default", "1");
+    }
+    extra_attrs(out, &memory.extra_attributes);
+    out.push_str("/>\n");
+}
+
+impl Device {
+    /// This device as a PDSC `<device>` element, with its processors, debug configuration,
+    /// memories and algorithms. A device that is a `<variant>` is written as a `<device>` named
+    /// after its [`base_device`](Device::base_device) declaring all of these, around a single
+    /// `<variant>`.
+    ///
+    /// The family, sub-family and vendor are attributes of the enclosing `<family>` and
+    /// `<subFamily>` elements and are left for the caller to write. Parsing the fragment within
+    /// those yields this device again, provided its [boot core](Device::boot_core) is the first
+    /// processor and it wasn't parsed with [`ParseOptions::collapse_variants`].
+    ///
+    /// [`ParseOptions::collapse_variants`]: crate::utils::ParseOptions::collapse_variants
+    pub fn to_pdsc_fragment(&self) -> String {
+        // The silicon revision goes on the `Dname`, which variants inherit it from.
+        let with_revision = |name: &str| match &self.silicon_revision {
+            Some(revision) => format!("{}@{}", name, revision),
+            None => name.to_string(),
+        };
+        // A variant only names itself and carries its condition and description; everything
+        // else is declared by the enclosing device.
+        let mut own = String::new();
+        if let Some(condition) = &self.condition {
+            attr(&mut own, "condition", condition);
+        }
+        extra_attrs(&mut own, &self.extra_attributes);
+        let description = self
+            .description
+            .as_ref()
+            .map(|text| format!("<description>{}</description>", escape(text)));
+
+        let mut out = String::from("<device");
+        match &self.base_device {
+            Some(base) => {
+                attr(&mut out, "Dname", &with_revision(base));
+                out.push_str(">\n");
+            }
+            None => {
+                attr(&mut out, "Dname", &with_revision(&self.name));
+                out.push_str(&own);
+                out.push_str(">\n");
+                if let Some(description) = &description {
+                    let _ = writeln!(out, "  {}", description);
+                }
+            }
+        }
+        // Units are only spelled out for processors that need telling apart from another of the
+        // same name.
+        let scoped_unit = |name: &Option<String>, unit: usize| {
+            let units = self.processors.iter().filter(|p| p.name == *name).count();
+            (units > 1 || unit != 0).then_some(unit)
+        };
+        for processor in &self.processors {
+            out.push_str("  <processor");
+            if let Some(name) = &processor.name {
+                attr(&mut out, "Pname", name);
+            }
+            if let Some(unit) = scoped_unit(&processor.name, processor.unit) {
+                attr(&mut out, "Punit", &unit.to_string());
+            }
+            attr(&mut out, "Dcore", &processor.core.to_string());
+            if let Some(revision) = &processor.core_revision {
+                attr(&mut out, "Dcorever", revision);
+            }
+            match processor.fpu {
+                FPU::None => {}
+                FPU::SinglePrecision => attr(&mut out, "Dfpu", "SP_FPU"),
+                FPU::DoublePrecision => attr(&mut out, "Dfpu", "DP_FPU"),
+            }
+            if processor.mpu == MPU::Present {
+                attr(&mut out, "Dmpu", "MPU");
+            }
+            if let Some(regions) = processor.mpu_regions {
+                attr(&mut out, "DnumMpuRegions", &regions.to_string());
+            }
+            if processor.tz == TrustZone::Present {
+                attr(&mut out, "Dtz", "TZ");
+            }
+            if processor.endian != Endian::Little {
+                attr(&mut out, "Dendian", &processor.endian.to_string());
+            }
+            extra_attrs(&mut out, &processor.extra_attributes);
+            out.push_str("/>\n");
+        }
+
+        // Access ports given by address need `<accessportV2>` elements, and once there are any,
+        // every `<debug>` has to refer to one by id.
+        let by_id = self.processors.iter().any(|p| p.ap.is_address());
+        if by_id {
+            for (id, processor) in self.processors.iter().enumerate() {
+                match processor.ap {
+                    AccessPort::Index(index) => {
+                        out.push_str("  <accessportV1");
+                        attr(&mut out, "__apid", &id.to_string());
+                        attr(&mut out, "__dp", &processor.dp.to_string());
+                        attr(&mut out, "index", &index.to_string());
+                    }
+                    AccessPort::Address(address) => {
+                        out.push_str("  <accessportV2");
+                        attr(&mut out, "__apid", &id.to_string());
+                        attr(&mut out, "__dp", &processor.dp.to_string());
+                        hex_attr(&mut out, "address", address);
+                        if let Some(targetsel) = processor.targetsel {
+                            hex_attr(&mut out, "__targetsel", targetsel.into());
+                        }
+                    }
+                }
+                out.push_str("/>\n");
+            }
+        }
+        for (id, processor) in self.processors.iter().enumerate() {
+            out.push_str("  <debug");
+            if let Some(name) = &processor.name {
+                attr(&mut out, "Pname", name);
+            }
+            if let Some(unit) = scoped_unit(&processor.name, processor.unit) {
+                attr(&mut out, "Punit", &unit.to_string());
+            }
+            if by_id {
+                attr(&mut out, "__apid", &id.to_string());
+            } else {
+                attr(&mut out, "__dp", &processor.dp.to_string());
+                attr(&mut out, "__ap", &processor.ap.as_u64().to_string());
+            }
+            if let (Some(targetsel), false) = (processor.targetsel, processor.ap.is_address()) {
+                hex_attr(&mut out, "__targetsel", targetsel.into());
+            }
+            if let Some(address) = processor.address {
+                hex_attr(&mut out, "address", address);
+            }
+            if let Some(svd) = &processor.svd {
+                attr(&mut out, "svd", svd);
+            }
+            if let Some(sequence) = &processor.default_reset_sequence {
+                attr(&mut out, "defaultResetSequence", sequence);
+            }
+            out.push_str("/>\n");
+        }
+
+        let mut memories: Vec<&Memory> = self.memories.0.values().collect();
+        memories.sort_by_key(|m| (m.start, &m.name));
+        for m in memories {
+            memory(&mut out, m);
+        }
+
+        for algorithm in &self.algorithms {
+            out.push_str("  <algorithm");
+            attr(
+                &mut out,
+                "name",
+                &algorithm.file_name.to_string_lossy().replace('\\', "/"),
+            );
+            hex_attr(&mut out, "start", algorithm.start);
+            hex_attr(&mut out, "size", algorithm.size);
+            if let Some(ram_start) = algorithm.ram_start {
+                hex_attr(&mut out, "RAMstart", ram_start);
+            }
+            if let Some(ram_size) = algorithm.ram_size {
+                hex_attr(&mut out, "RAMsize", ram_size);
+            }
+            if algorithm.default {
+                attr(&mut out, "default", "1");
+            }
+            match algorithm.style {
+                AlgorithmStyle::Keil => {}
+                AlgorithmStyle::IAR => attr(&mut out, "style", "IAR"),
+                AlgorithmStyle::CMSIS => attr(&mut out, "style", "CMSIS"),
+            }
+            if let Some(name) = &algorithm.p_name {
+                attr(&mut out, "Pname", name);
+            }
+            if let Some(condition) = &algorithm.condition {
+                attr(&mut out, "condition", condition);
+            }
+            out.push_str("/>\n");
+        }
+
+        if self.base_device.is_some() {
+            out.push_str("  <variant");
+            attr(&mut out, "Dvariant", &self.name);
+            out.push_str(&own);
+            match &description {
+                Some(description) => {
+                    let _ = writeln!(out, ">\n    {}\n  </variant>", description);
+                }
+                None => out.push_str("/>\n"),
+            }
+        }
+        out.push_str("</device>\n");
+        out
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::pdsc::Devices;
+    use crate::utils::prelude::*;
+
+    const FAMILY: &str = r#"<devices>
+      <family Dfamily="STM32H7 Series" Dvendor="STMicroelectronics:13">
+        <description>Dual core   STM32H7 &amp; friends</description>
+        <processor Pname="CM7" Dcore="Cortex-M7" Dfpu="DP_FPU" Dmpu="MPU" Dcorever="r1p1"/>
+        <processor Pname="CM4" Dcore="Cortex-M4" Dfpu="SP_FPU" Dmpu="MPU" DnumMpuRegions="8"/>
+        <debug Pname="CM7" __dp="0" __ap="0" svd="CMSIS/SVD/STM32H745_CM7.svd"/>
+        <debug Pname="CM4" __dp="0" __ap="3" svd="CMSIS/SVD/STM32H745_CM4.svd" address="0xE0000000"/>
+        <memory name="FLASH" access="rx" start="0x08000000" size="0x100000" startup="1" default="1"/>
+        <memory name="FLASH_CM4" Pname="CM4" access="rx" start="0x08100000" size="0x100000" startup="1"/>
+        <memory name="DTCM" Pname="CM7" access="rw" start="0x20000000" size="0x20000" default="1"/>
+        <memory name="SRAM_NS" access="rwn" start="0x30000000" size="0x20000"/>
+        <algorithm name="CMSIS\Flash\STM32H7x_2048.FLM" start="0x08000000" size="0x200000" RAMstart="0x20000000" RAMsize="0x1000" default="1"/>
+        <algorithm name="CMSIS/Flash/STM32H7x_OTP.FLM" start="0x08FFF000" size="0x400" style="IAR"/>
+        <subFamily DsubFamily="STM32H745">
+          <device Dname="STM32H745@Y" condition="H7 GCC">
+            <variant Dvariant="STM32H745ZITx"/>
+          </device>
+        </subFamily>
+      </family>
+    </devices>"#;
+
+    fn reparse(fragment: &str, device: &Device) -> Devices {
+        let mut xml = format!("<devices><family Dfamily=\"{}\"", escape(&device.family));
+        if let Some(vendor) = &device.vendor {
+            xml += &format!(" Dvendor=\"{}\"", escape(vendor));
+        }
+        xml += ">";
+        if let Some(sub_family) = &device.sub_family {
+            xml += &format!("<subFamily DsubFamily=\"{}\">", escape(sub_family));
+        }
+        xml += fragment;
+        if device.sub_family.is_some() {
+            xml += "</subFamily>";
+        }
+        xml += "</family></devices>";
+        Devices::from_string(&xml).unwrap()
+    }
+
+    #[test]
+    fn round_trip_variant() {
+        let devices = Devices::from_string(FAMILY).unwrap();
+        let device = &devices.0["STM32H745ZITx"];
+        let fragment = device.to_pdsc_fragment();
+        assert_eq!(fragment, include_str!("testdata/stm32h745_device.pdsc"));
+        assert_eq!(&reparse(&fragment, device).0["STM32H745ZITx"], device);
+    }
+
+    #[test]
+    fn round_trip_units_and_access_ports() {
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="Family">
+                <processor Pname="cl" Dcore="Cortex-A53" Punits="2"/>
+                <processor Pname="rt" Dcore="Cortex-M0+"/>
+                <accessportV2 __apid="1" __dp="0" address="0x80002000"/>
+                <accessportV1 __apid="2" __dp="1" index="4"/>
+                <debug Pname="cl" __apid="1"/>
+                <debug Pname="rt" __apid="2" defaultResetSequence="ResetSystem"/>
+                <memory id="IRAM1" Pname="cl" Punit="1" start="0x0" size="0x1000"/>
+                <device Dname="Device"/>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        let device = &devices.0["Device"];
+        let fragment = device.to_pdsc_fragment();
+        assert!(fragment.contains("<accessportV2 __apid=\"0\" __dp=\"0\" address=\"0x80002000\"/>"));
+        assert!(fragment.contains("start=\"0x00000000\""));
+        assert_eq!(&reparse(&fragment, device).0["Device"], device);
+    }
+}
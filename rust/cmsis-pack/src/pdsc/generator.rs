@@ -0,0 +1,77 @@
+use anyhow::{format_err, Error};
+use roxmltree::Node;
+use serde::Serialize;
+
+use crate::utils::prelude::*;
+
+/// An external tool, such as STM32CubeMX, used to generate device configuration.
+#[derive(Debug, Clone, Serialize)]
+pub struct Generator {
+    pub id: String,
+    pub command: String,
+    pub working_dir: Option<String>,
+}
+
+impl FromElem for Generator {
+    fn from_elem(e: &Node) -> Result<Self, Error> {
+        assert_root_name(e, "generator")?;
+        let id: String = attr_map(e, "id")?;
+        // Older packs put <command> directly in the generator, newer ones put one in each
+        // host specific <exe> element.
+        let command = child_text(e, "command")
+            .ok()
+            .or_else(|| {
+                e.children()
+                    .filter(|c| c.tag_name().name() == "exe")
+                    .find_map(|exe| child_text(&exe, "command").ok())
+            })
+            .map(|command| command.trim().to_string())
+            .ok_or_else(|| format_err!("No command found for generator {}", id))?;
+        Ok(Self {
+            id,
+            command,
+            working_dir: child_text(e, "workingDir")
+                .ok()
+                .map(|dir| dir.trim().to_string()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn generator_with_command() {
+        let generator = Generator::from_string(
+            r#"<generator id="STM32CubeMX" Gvendor="STMicroelectronics">
+                 <description>STM32CubeMX Environment</description>
+                 <command>$SMDK\CubeMX\STM32CubeMXLauncher</command>
+                 <workingDir>$PRTE\Device\$D</workingDir>
+               </generator>"#,
+        )
+        .unwrap();
+        assert_eq!(generator.id, "STM32CubeMX");
+        assert_eq!(generator.command, "$SMDK\\CubeMX\\STM32CubeMXLauncher");
+        assert_eq!(generator.working_dir.as_deref(), Some("$PRTE\\Device\\$D"));
+    }
+
+    #[test]
+    fn generator_with_exe() {
+        let generator = Generator::from_string(
+            r#"<generator id="Tool">
+                 <exe host="win"><command>tool.exe</command></exe>
+                 <exe host="linux"><command>tool</command></exe>
+               </generator>"#,
+        )
+        .unwrap();
+        assert_eq!(generator.command, "tool.exe");
+        assert_eq!(generator.working_dir, None);
+    }
+
+    #[test]
+    fn generator_without_command() {
+        assert!(Generator::from_string(r#"<generator id="Tool"/>"#).is_err());
+        assert!(Generator::from_string(r#"<generator><command>x</command></generator>"#).is_err());
+    }
+}
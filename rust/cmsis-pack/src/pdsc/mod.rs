@@ -9,16 +9,47 @@ use std::path::Path;
 use crate::utils::prelude::*;
 use anyhow::{format_err, Error};
 
+mod board;
 mod component;
 mod condition;
+mod custom;
 mod device;
+mod device_name;
+mod filter;
+mod fragment;
+mod generator;
+#[cfg(feature = "probe-rs")]
+mod probe_rs;
+#[cfg(feature = "svd")]
+mod svd;
+mod validate;
+pub use board::{Board, Boards};
 pub use component::{ComponentBuilders, FileRef};
-pub use condition::{Condition, Conditions};
-pub use device::{AccessPort, Algorithm, Core, Device, Devices, Memories, Processor};
+pub use condition::{Condition, ConditionComponent, ConditionContext, Conditions};
+pub use custom::CustomDeviceBuilder;
+pub use device::{
+    format_bytes, AccessPort, AccessPortNode, Algorithm, AlgorithmStyle, Architecture, Core, Debug,
+    DebugPortNode, DebugTopology, Device, DeviceMeta, Devices, Endian, FlashBank, FlashProfile,
+    IndexStats, Memories, Memory, MemoryKind, MemoryPermissions, Processor, SecurityState,
+    TrustZone, WellKnownMemory, FPU, MPU,
+};
+pub use device_name::{DeviceName, DeviceNameParts};
+pub use filter::DeviceFilter;
+pub use generator::Generator;
+#[cfg(feature = "svd")]
+pub use svd::Peripheral;
+pub use validate::{MemoryOverlap, MissingFile, ValidationIssue};
 
 pub struct Release {
     pub version: String,
     pub text: String,
+    /// The release carries a `deprecated` date: the pack is no longer maintained.
+    pub deprecated: bool,
+    /// The pack, as `Vendor.Name`, that supersedes a deprecated pack.
+    pub replacement: Option<String>,
+    /// Where this release's pack may be downloaded from, when it isn't at the usual place below
+    /// the package `<url>`. May be relative to that `<url>`.
+    pub url: Option<String>,
 }
 
 impl FromElem for Release {
@@ -26,7 +57,10 @@ impl FromElem for Release {
         assert_root_name(e, "release")?;
         Ok(Self {
             version: attr_map(e, "version")?,
-            text: e.text().unwrap().to_string(),
+            text: e.text().unwrap_or_default().to_string(),
+            deprecated: e.attribute("deprecated").is_some(),
+            replacement: attr_map(e, "replacement").ok(),
+            url: attr_map(e, "url").ok(),
         })
     }
 }
@@ -35,8 +69,9 @@ impl FromElem for Release {
 pub struct Releases(Vec<Release>);
 
 impl Releases {
-    pub fn latest_release(&self) -> &Release {
-        &self.0[0]
+    /// The first, and by convention newest, release; `None` for a pack without releases.
+    pub fn latest_release(&self) -> Option<&Release> {
+        self.0.first()
     }
 }
 
@@ -60,8 +95,8 @@ impl FromElem for Releases {
 pub struct DumpDevice<'a> {
     name: &'a str,
     memories: Cow<'a, Memories>,
-    algorithms: Cow<'a, Vec<Algorithm>>,
-    processors: Cow<'a, Vec<Processor>>,
+    algorithms: Cow<'a, [Algorithm]>,
+    processors: Cow<'a, [Processor]>,
     from_pack: FromPack<'a>,
     vendor: Option<&'a str>,
     family: &'a str,
@@ -102,86 +137,199 @@ impl<'a> DumpDevice<'a> {
     }
 }
 
-pub struct Package {
+/// The identifying information of a pack: who publishes it, where it lives and its releases.
+pub struct PackInfo {
     pub name: String,
     pub description: String,
     pub vendor: String,
     pub url: String,
     pub license: Option<String>,
-    components: ComponentBuilders,
     pub releases: Releases,
+    /// The CMSIS-Pack schema version the description was written against, from the
+    /// `schemaVersion` attribute, e.g. `1.7.28`.
+    pub schema_version: Option<String>,
+}
+
+impl PackInfo {
+    /// Returns true if the latest release deprecates this pack.
+    pub fn is_deprecated(&self) -> bool {
+        self.releases.0.first().is_some_and(|r| r.deprecated)
+    }
+
+    /// The pack replacing this one, as named by the latest release.
+    pub fn replacement(&self) -> Option<&str> {
+        self.releases.0.first()?.replacement.as_deref()
+    }
+
+    /// The URL the pack of `version` is downloaded from.
+    ///
+    /// This is the `url` of that release when it has one, resolved against the package `<url>`
+    /// if relative. Otherwise it is the pack file, `Vendor.Name.version.pack`, below the package
+    /// `<url>`. Returns `None` if there is no package `<url>` to resolve against.
+    pub fn download_url(&self, version: &str) -> Option<String> {
+        let release_url = self
+            .releases
+            .0
+            .iter()
+            .find(|r| r.version == version)
+            .and_then(|r| r.url.as_deref());
+        match release_url {
+            Some(url) if url.contains("://") => Some(url.to_string()),
+            _ if self.url.is_empty() => None,
+            Some(url) => Some(join_url(&self.url, url)),
+            None => Some(join_url(
+                &self.url,
+                &format!("{}.{}.{}.pack", self.vendor, self.name, version),
+            )),
+        }
+    }
+}
+
+/// Joins the relative `path` onto `base` with a single `/` between them.
+fn join_url(base: &str, path: &str) -> String {
+    format!(
+        "{}/{}",
+        base.trim_end_matches('/'),
+        path.trim_start_matches("./")
+    )
+}
+
+impl FromElem for PackInfo {
+    fn from_elem(e: &Node) -> Result<Self, Error> {
+        assert_root_name(e, "package")?;
+        let releases = match e.children().find(|c| c.tag_name().name() == "releases") {
+            Some(child) => Releases::from_elem(&child).ok_warn().unwrap_or_default(),
+            None => Releases::default(),
+        };
+        Ok(Self {
+            name: child_text(e, "name")?,
+            description: child_text(e, "description")?,
+            vendor: child_text(e, "vendor")?,
+            url: child_text(e, "url")?,
+            license: child_text(e, "license").ok(),
+            releases,
+            schema_version: attr_map(e, "schemaVersion").ok(),
+        })
+    }
+}
+
+/// A whole PDSC file, parsed from its `<package>` root element.
+pub struct Pack {
+    pub info: PackInfo,
+    components: ComponentBuilders,
     pub conditions: Conditions,
     pub devices: Devices,
-    pub boards: Vec<Board>,
+    pub boards: Boards,
+    pub generators: Vec<Generator>,
+    pub taxonomy: Vec<TaxonomyEntry>,
 }
 
-impl FromElem for Package {
+/// The previous name of [`Pack`].
+#[deprecated(note = "renamed to `Pack`")]
+pub type Package = Pack;
+
+impl FromElem for Pack {
     fn from_elem(e: &Node) -> Result<Self, Error> {
-        assert_root_name(e, "package")?;
-        let name: String = child_text(e, "name")?;
-        let description: String = child_text(e, "description")?;
-        let vendor: String = child_text(e, "vendor")?;
-        let url: String = child_text(e, "url")?;
-        log::debug!("Working on {}::{}", vendor, name,);
+        Self::from_elem_with(e, &ParseOptions::default())
+    }
+
+    fn from_elem_with(e: &Node, opts: &ParseOptions) -> Result<Self, Error> {
+        Self::from_elem_with_diagnostics(e, opts, &mut LogDiagnostics)
+    }
+
+    fn from_elem_with_diagnostics(
+        e: &Node,
+        opts: &ParseOptions,
+        diags: &mut dyn Diagnostics,
+    ) -> Result<Self, Error> {
+        let info = PackInfo::from_elem(e)?;
+        log::debug!("Working on {}::{}", info.vendor, info.name);
+        if info.is_deprecated() {
+            match info.replacement() {
+                Some(replacement) => log::warn!(
+                    "{}::{} is deprecated, use {}",
+                    info.vendor,
+                    info.name,
+                    replacement
+                ),
+                None => log::warn!("{}::{} is deprecated", info.vendor, info.name),
+            }
+        }
         let mut components = ComponentBuilders::default();
-        let mut releases = Releases::default();
         let mut conditions = Conditions::default();
         let mut devices = Devices::default();
-        let mut boards: Vec<Board> = Vec::new();
+        let mut boards = Boards::default();
+        let mut generators: Vec<Generator> = Vec::new();
+        let mut taxonomy: Vec<TaxonomyEntry> = Vec::new();
         for child in e.children() {
             match child.tag_name().name() {
                 "components" => {
                     components = ComponentBuilders::from_elem(&child)
-                        .ok_warn()
+                        .ok_report(&child, diags)
                         .unwrap_or_default();
                 }
-                "releases" => {
-                    releases = Releases::from_elem(&child).ok_warn().unwrap_or_default();
-                }
                 "conditions" => {
-                    conditions = Conditions::from_elem(&child).ok_warn().unwrap_or_default();
+                    conditions = Conditions::from_elem(&child)
+                        .ok_report(&child, diags)
+                        .unwrap_or_default();
                 }
                 "devices" => {
-                    devices = Devices::from_elem(&child).ok_warn().unwrap_or_default();
+                    devices = Devices::from_elem_with_diagnostics(&child, opts, diags)
+                        .ok_report(&child, diags)
+                        .unwrap_or_default();
                 }
                 "boards" => {
-                    boards = Board::vec_from_children(child.children());
+                    boards = Boards::from_elem_with_diagnostics(&child, opts, diags)
+                        .ok_report(&child, diags)
+                        .unwrap_or_default();
+                }
+                "generators" => {
+                    generators = Generator::vec_from_children(child.children());
+                }
+                "taxonomy" => {
+                    taxonomy = TaxonomyEntry::vec_from_children(child.children());
                 }
                 _ => {}
             }
         }
+        if let Some(release) = info.releases.latest_release() {
+            for device in devices.0.values_mut() {
+                device.pack_version = Some(release.version.clone());
+            }
+        }
         Ok(Self {
-            name,
-            description,
-            vendor,
-            url,
+            info,
             components,
-            license: child_text(e, "license").ok(),
-            releases,
             conditions,
             devices,
             boards,
+            generators,
+            taxonomy,
         })
     }
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-pub struct Board {
-    name: String,
-    mounted_devices: Vec<String>,
+/// A `<description>` of the `<taxonomy>`, describing a component class or group.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TaxonomyEntry {
+    pub class: String,
+    pub group: Option<String>,
+    /// Documentation for the class or group, a path in the pack or a URL.
+    pub doc: Option<String>,
+    pub description: String,
 }
 
-impl FromElem for Board {
+impl FromElem for TaxonomyEntry {
     fn from_elem(e: &Node) -> Result<Self, Error> {
+        assert_root_name(e, "description")?;
         Ok(Self {
-            name: attr_map(e, "name")?,
-            mounted_devices: e
-                .children()
-                .flat_map(|c| match c.tag_name().name() {
-                    "mountedDevice" => attr_map(&c, "Dname").ok(),
-                    _ => None,
-                })
-                .collect(),
+            class: attr_map(e, "Cclass")?,
+            group: attr_map(e, "Cgroup").ok(),
+            doc: attr_map(e, "doc").ok(),
+            description: e
+                .text()
+                .map(|text| text.split_whitespace().collect::<Vec<_>>().join(" "))
+                .unwrap_or_default(),
         })
     }
 }
@@ -206,21 +354,39 @@ pub struct Component {
 
 type Components = Vec<Component>;
 
-impl Package {
+impl Pack {
+    /// Parse a whole PDSC file from its text.
+    pub fn from_pdsc_str(pdsc: &str) -> Result<Self, Error> {
+        Self::from_string(pdsc)
+    }
+
     pub fn make_components(&self) -> Components {
         self.components
             .0
             .clone()
             .into_iter()
-            .map(|comp| Component {
-                vendor: comp.vendor.unwrap_or_else(|| self.vendor.clone()),
-                class: comp.class.unwrap(),
-                group: comp.group.unwrap(),
+            .filter_map(|comp| match (comp.class.clone(), comp.group.clone()) {
+                (Some(class), Some(group)) => Some((comp, class, group)),
+                _ => {
+                    log::warn!("Component found without a Cclass or Cgroup");
+                    None
+                }
+            })
+            .map(|(comp, class, group)| Component {
+                vendor: comp.vendor.unwrap_or_else(|| self.info.vendor.clone()),
+                class,
+                group,
                 sub_group: comp.sub_group,
                 variant: comp.variant,
                 version: comp
                     .version
-                    .unwrap_or_else(|| self.releases.latest_release().version.clone()),
+                    .or_else(|| {
+                        self.info
+                            .releases
+                            .latest_release()
+                            .map(|r| r.version.clone())
+                    })
+                    .unwrap_or_default(),
                 api_version: comp.api_version,
                 condition: comp.condition,
                 max_instances: comp.max_instances,
@@ -234,21 +400,48 @@ impl Package {
     }
 
     pub fn make_condition_lookup(&self) -> HashMap<&str, &Condition> {
-        let mut map = HashMap::with_capacity(self.conditions.0.len());
-        for cond in self.conditions.0.iter() {
-            if let Some(dup) = map.insert(cond.id.as_str(), cond) {
-                log::warn!("Duplicate Condition found {}", dup.id);
-            }
-        }
-        map
+        self.conditions
+            .0
+            .iter()
+            .map(|(id, cond)| (id.as_str(), cond))
+            .collect()
+    }
+
+    /// The boards carrying the device `dname`, whoever made the board, sorted by name.
+    pub fn boards_for_device(&self, dname: &str) -> Vec<&Board> {
+        self.boards.for_device(dname)
+    }
+
+    /// The devices present for `ctx`: those without a `condition`, or whose condition holds.
+    ///
+    /// Devices gated by a condition this pack doesn't declare are left out.
+    pub fn devices_matching(&self, ctx: &ConditionContext) -> Vec<&Device> {
+        let conditions = self.make_condition_lookup();
+        self.devices
+            .0
+            .values()
+            .filter(|device| match device.condition.as_deref() {
+                None => true,
+                Some(id) => match conditions.get(id) {
+                    Some(condition) => condition.evaluate(ctx, device, &conditions),
+                    None => {
+                        log::warn!("{}: unknown condition {}", device.name, id);
+                        false
+                    }
+                },
+            })
+            .collect()
     }
 
     pub fn make_dump_devices(&self) -> Vec<(&str, DumpDevice<'_>)> {
         let from_pack = FromPack::new(
-            &self.vendor,
-            &self.name,
-            &self.releases.latest_release().version,
-            &self.url,
+            &self.info.vendor,
+            &self.info.name,
+            self.info
+                .releases
+                .latest_release()
+                .map_or("", |r| r.version.as_str()),
+            &self.info.url,
         );
         self.devices
             .0
@@ -257,12 +450,12 @@ impl Package {
             .collect()
     }
 }
-pub fn dump_devices<'a, P: AsRef<Path>, I: IntoIterator<Item = &'a Package>>(
+pub fn dump_devices<'a, P: AsRef<Path>, I: IntoIterator<Item = &'a Pack>>(
     pdscs: I,
     device_dest: Option<P>,
     board_dest: Option<P>,
 ) -> Result<(), Error> {
-    let pdscs: Vec<&Package> = pdscs.into_iter().collect();
+    let pdscs: Vec<&Pack> = pdscs.into_iter().collect();
     let devices = pdscs
         .iter()
         .flat_map(|pdsc| pdsc.make_dump_devices().into_iter())
@@ -294,7 +487,7 @@ pub fn dump_devices<'a, P: AsRef<Path>, I: IntoIterator<Item = &'a Package>>(
     }
     let boards = pdscs
         .iter()
-        .flat_map(|pdsc| pdsc.boards.iter())
+        .flat_map(|pdsc| pdsc.boards.0.values())
         .map(|b| (&b.name, b))
         .collect::<HashMap<_, _>>();
     match board_dest {
@@ -325,7 +518,7 @@ pub fn dump_devices<'a, P: AsRef<Path>, I: IntoIterator<Item = &'a Package>>(
 
 pub fn dumps_components<'a, I>(pdscs: I) -> Result<String, Error>
 where
-    I: IntoIterator<Item = &'a Package>,
+    I: IntoIterator<Item = &'a Pack>,
 {
     let components = pdscs
         .into_iter()
@@ -333,3 +526,295 @@ where
         .collect::<Vec<_>>();
     Ok(serde_json::to_string_pretty(&components)?)
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const PDSC: &str = r#"
+        <package schemaVersion="1.7.28">
+          <vendor>Vendor</vendor>
+          <name>Pack</name>
+          <description>A pack</description>
+          <url>http://example.com/packs/</url>
+          <releases>
+            <release version="1.1.0">Second</release>
+            <release version="1.0.0">First</release>
+          </releases>
+          <conditions>
+            <condition id="Cond">
+              <require Dvendor="Vendor:1"/>
+            </condition>
+          </conditions>
+          <devices>
+            <family Dfamily="Family" Dvendor="Vendor:1">
+              <processor Dcore="Cortex-M4"/>
+              <device Dname="Device"/>
+            </family>
+          </devices>
+          <generators>
+            <generator id="Tool">
+              <command>tool</command>
+            </generator>
+          </generators>
+          <taxonomy>
+            <description Cclass="Device" doc="Docs/Device.html">Startup, System Setup</description>
+            <description Cclass="Device" Cgroup="HAL">
+              Hardware abstraction layer
+            </description>
+            <description Cgroup="Orphan">No class</description>
+          </taxonomy>
+          <boards>
+            <board name="Board" vendor="Vendor">
+              <mountedDevice Dname="Device"/>
+              <mountedDevice Dname="Missing"/>
+              <debugInterface adapter="CMSIS-DAP" connector="USB"/>
+              <memory name="SDRAM" access="rw" start="0xC0000000" size="0x800000"/>
+            </board>
+          </boards>
+        </package>
+    "#;
+
+    #[test]
+    fn package_parses_all_sections() {
+        let pack = Pack::from_pdsc_str(PDSC).unwrap();
+        assert_eq!(pack.info.vendor, "Vendor");
+        assert_eq!(pack.info.name, "Pack");
+        assert_eq!(pack.info.url, "http://example.com/packs/");
+        assert_eq!(pack.info.schema_version.as_deref(), Some("1.7.28"));
+        assert_eq!(
+            pack.info.releases.latest_release().unwrap().version,
+            "1.1.0"
+        );
+        assert_eq!(pack.conditions.0.len(), 1);
+        assert_eq!(
+            pack.devices.0["Device"].pack_version.as_deref(),
+            Some("1.1.0")
+        );
+        assert_eq!(pack.boards.0.len(), 1);
+        assert_eq!(pack.generators.len(), 1);
+        assert_eq!(pack.taxonomy.len(), 2);
+    }
+
+    #[test]
+    fn schema_version_is_optional() {
+        let info = PackInfo::from_string(
+            r#"<package>
+              <vendor>Vendor</vendor>
+              <name>Pack</name>
+              <description>A pack</description>
+              <url>http://example.com/packs/</url>
+            </package>"#,
+        )
+        .unwrap();
+        assert_eq!(info.schema_version, None);
+    }
+
+    #[test]
+    fn taxonomy_entries() {
+        let pack = Pack::from_string(PDSC).unwrap();
+        let device = &pack.taxonomy[0];
+        assert_eq!(device.class, "Device");
+        assert_eq!(device.group, None);
+        assert_eq!(device.doc.as_deref(), Some("Docs/Device.html"));
+        assert_eq!(device.description, "Startup, System Setup");
+        let hal = &pack.taxonomy[1];
+        assert_eq!(hal.group.as_deref(), Some("HAL"));
+        assert_eq!(hal.description, "Hardware abstraction layer");
+    }
+
+    #[test]
+    fn board_memories_stay_apart() {
+        let pack = Pack::from_string(PDSC).unwrap();
+        let board = &pack.boards.0["Board"];
+        let sdram = &board.extra_memories.0["SDRAM"];
+        assert_eq!((sdram.start, sdram.size), (0xC000_0000, 0x80_0000));
+        assert!(sdram.access.write);
+        let device = board.devices(&pack.devices).next().unwrap();
+        assert!(!device.memories.0.contains_key("SDRAM"));
+    }
+
+    #[test]
+    fn devices_gated_by_condition() {
+        let pack = Pack::from_string(
+            r#"<package>
+              <vendor>Vendor</vendor>
+              <name>Pack</name>
+              <description>A pack</description>
+              <url>http://example.com/packs/</url>
+              <releases>
+                <release version="1.0.0">First</release>
+              </releases>
+              <conditions>
+                <condition id="GCC">
+                  <require Tcompiler="GCC"/>
+                </condition>
+              </conditions>
+              <devices>
+                <family Dfamily="Family" Dvendor="Vendor:1">
+                  <processor Dcore="Cortex-M4"/>
+                  <device Dname="Always"/>
+                  <device Dname="GccOnly" condition="GCC"/>
+                  <device Dname="Unknown" condition="Missing"/>
+                </family>
+              </devices>
+            </package>"#,
+        )
+        .unwrap();
+        let names = |ctx: &ConditionContext| {
+            let mut names: Vec<&str> = pack
+                .devices_matching(ctx)
+                .into_iter()
+                .map(|d| d.name.as_str())
+                .collect();
+            names.sort();
+            names
+        };
+        let gcc = ConditionContext {
+            compiler: Some("GCC".into()),
+        };
+        let armcc = ConditionContext {
+            compiler: Some("ARMCC".into()),
+        };
+        assert_eq!(names(&gcc), ["Always", "GccOnly"]);
+        assert_eq!(names(&armcc), ["Always"]);
+        assert_eq!(pack.devices.0["GccOnly"].condition.as_deref(), Some("GCC"));
+    }
+
+    #[test]
+    fn deprecated_release() {
+        let pack = Pack::from_string(PDSC).unwrap();
+        assert!(!pack.info.is_deprecated());
+        assert_eq!(pack.info.replacement(), None);
+
+        let deprecated = PackInfo::from_string(
+            r#"<package>
+              <vendor>Vendor</vendor>
+              <name>Old</name>
+              <description>An old pack</description>
+              <url>http://example.com/packs/</url>
+              <releases>
+                <release version="2.0.0" deprecated="2023-01-01" replacement="Vendor.New">
+                  Deprecated
+                </release>
+                <release version="1.0.0">First</release>
+              </releases>
+            </package>"#,
+        )
+        .unwrap();
+        assert!(deprecated.is_deprecated());
+        assert_eq!(deprecated.replacement(), Some("Vendor.New"));
+        assert!(!deprecated.releases.0[1].deprecated);
+    }
+
+    #[test]
+    fn board_links_mounted_devices() {
+        let pack = Pack::from_string(PDSC).unwrap();
+        let board = &pack.boards.0["Board"];
+        assert_eq!(board.vendor.as_deref(), Some("Vendor"));
+        assert_eq!(board.debug_interface.as_deref(), Some("CMSIS-DAP"));
+        assert_eq!(board.mounted_devices, vec!["Device", "Missing"]);
+        let mounted: Vec<_> = board.devices(&pack.devices).map(|d| &d.name).collect();
+        assert_eq!(mounted, vec!["Device"]);
+    }
+
+    #[test]
+    fn third_party_board_for_device() {
+        let pack = Pack::from_string(
+            r#"<package>
+              <vendor>Keil</vendor>
+              <name>STM32F4xx_DFP</name>
+              <description>STM32F4 devices</description>
+              <url>http://example.com/packs/</url>
+              <devices>
+                <family Dfamily="STM32F4 Series" Dvendor="STMicroelectronics:13">
+                  <processor Dcore="Cortex-M4"/>
+                  <device Dname="STM32F407VG"/>
+                  <device Dname="STM32F429ZI"/>
+                </family>
+              </devices>
+              <boards>
+                <board name="STM32F4-Discovery" vendor="STMicroelectronics">
+                  <mountedDevice deviceIndex="0" Dvendor="STMicroelectronics:13" Dname="STM32F407VG"/>
+                </board>
+                <board name="Arch Max" vendor="Seeed">
+                  <mountedDevice deviceIndex="0" Dvendor="STMicroelectronics:13" Dname="STM32F407VG"/>
+                </board>
+                <board name="32F429IDISCOVERY" vendor="STMicroelectronics">
+                  <mountedDevice deviceIndex="0" Dvendor="STMicroelectronics:13" Dname="STM32F429ZI"/>
+                </board>
+              </boards>
+            </package>"#,
+        )
+        .unwrap();
+        let boards = pack.boards_for_device("STM32F407VG");
+        let names: Vec<&str> = boards.iter().map(|b| b.name.as_str()).collect();
+        assert_eq!(names, ["Arch Max", "STM32F4-Discovery"]);
+        let seeed = boards[0];
+        assert_eq!(seeed.vendor.as_deref(), Some("Seeed"));
+        assert_eq!(
+            seeed.mounted_device_vendors,
+            vec![Some("STMicroelectronics".to_string())]
+        );
+        let device = seeed.devices(&pack.devices).next().unwrap();
+        assert_eq!(device.vendor_name(), Some("STMicroelectronics"));
+        assert!(pack.boards_for_device("STM32F411RE").is_empty());
+    }
+
+    #[test]
+    fn pack_download_urls() {
+        let info = |url: &str| {
+            PackInfo::from_string(&format!(
+                r#"<package>
+                  <vendor>Vendor</vendor>
+                  <name>Pack</name>
+                  <description>A pack</description>
+                  <url>{}</url>
+                  <releases>
+                    <release version="1.2.0">Latest</release>
+                    <release version="1.1.0" url="https://mirror.example.com/Vendor.Pack.1.1.0.pack">
+                      Mirrored
+                    </release>
+                    <release version="1.0.0" url="archive/Vendor.Pack.1.0.0.pack">Archived</release>
+                  </releases>
+                </package>"#,
+                url
+            ))
+            .unwrap()
+        };
+        let absolute = info("https://example.com/packs/");
+        assert_eq!(
+            absolute.download_url("1.2.0").as_deref(),
+            Some("https://example.com/packs/Vendor.Pack.1.2.0.pack")
+        );
+        assert_eq!(
+            absolute.download_url("1.1.0").as_deref(),
+            Some("https://mirror.example.com/Vendor.Pack.1.1.0.pack")
+        );
+        assert_eq!(
+            absolute.download_url("1.0.0").as_deref(),
+            Some("https://example.com/packs/archive/Vendor.Pack.1.0.0.pack")
+        );
+        assert_eq!(
+            info("https://example.com/packs").download_url("1.2.0"),
+            absolute.download_url("1.2.0")
+        );
+
+        let relative = info("packs");
+        assert_eq!(
+            relative.download_url("1.2.0").as_deref(),
+            Some("packs/Vendor.Pack.1.2.0.pack")
+        );
+        assert_eq!(
+            relative.download_url("1.0.0").as_deref(),
+            Some("packs/archive/Vendor.Pack.1.0.0.pack")
+        );
+
+        let missing = info("");
+        assert_eq!(missing.download_url("1.2.0"), None);
+        assert_eq!(
+            missing.download_url("1.1.0").as_deref(),
+            Some("https://mirror.example.com/Vendor.Pack.1.1.0.pack")
+        );
+    }
+}
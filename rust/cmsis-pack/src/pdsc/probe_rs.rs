@@ -0,0 +1,199 @@
+use std::borrow::Cow;
+use std::fmt::Write;
+
+use super::{AccessPort, Architecture, Device, Processor};
+
+/// The probe-rs `CoreType` of `processor`, or `None` for cores probe-rs does not support.
+fn core_type(processor: &Processor) -> Option<&'static str> {
+    match processor.core.architecture() {
+        Architecture::Armv6M => Some("armv6m"),
+        Architecture::Armv7M => Some("armv7m"),
+        Architecture::Armv7EM => Some("armv7em"),
+        Architecture::Armv8MBaseline
+        | Architecture::Armv8MMainline
+        | Architecture::Armv81MMainline => Some("armv8m"),
+        Architecture::Armv7A => Some("armv7a"),
+        Architecture::Armv8A | Architecture::Armv82A => Some("armv8a"),
+        Architecture::Armv7R | Architecture::Any => None,
+    }
+}
+
+/// A plain YAML scalar, quoted when it contains anything beyond a conservative set of
+/// characters.
+fn scalar(value: &str) -> Cow<'_, str> {
+    let plain = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '/' | '+'))
+        && value.starts_with(|c: char| c.is_ascii_alphanumeric());
+    if plain {
+        Cow::Borrowed(value)
+    } else {
+        Cow::Owned(format!(
+            "\"{}\"",
+            value.replace('\\', "\\\\").replace('"', "\\\"")
+        ))
+    }
+}
+
+/// The name probe-rs refers to a flash algorithm by: its file stem in lower case, with anything
+/// but letters and digits replaced by `_`.
+fn algorithm_name(file_name: &std::path::Path) -> String {
+    file_name
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_default()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+impl Device {
+    /// This device as a probe-rs target description `variants` entry, in YAML.
+    ///
+    /// Processors are named `main` on single core devices, and by `Pname` (suffixed with the
+    /// unit for multi-unit processors) otherwise. Flash regions become `!Nvm` regions, other
+    /// writable regions `!Ram` regions and the remaining regions `!Generic` regions; peripheral
+    /// regions are left out. Flash algorithms are referenced by [`algorithm_name`], and have to
+    /// be added to the target description separately.
+    ///
+    /// Processors with a core probe-rs does not support are left out with a warning.
+    pub fn to_probe_rs_yaml(&self) -> String {
+        let single = self.processors.len() == 1;
+        let core_name = |processor: &Processor| -> String {
+            if single {
+                return "main".to_string();
+            }
+            let units = self
+                .processors
+                .iter()
+                .filter(|p| p.name == processor.name)
+                .count();
+            match (&processor.name, units) {
+                (Some(name), 1) => name.to_lowercase(),
+                (Some(name), _) => format!("{}_{}", name.to_lowercase(), processor.unit),
+                (None, _) => format!("core{}", processor.unit),
+            }
+        };
+        let cores: Vec<(&Processor, String, &str)> = self
+            .processors
+            .iter()
+            .filter_map(|processor| match core_type(processor) {
+                Some(kind) => Some((processor, core_name(processor), kind)),
+                None => {
+                    log::warn!(
+                        "{}: probe-rs does not support core {:?}",
+                        self.name,
+                        processor.core
+                    );
+                    None
+                }
+            })
+            .collect();
+
+        // Writing to a `String` cannot fail.
+        let mut out = String::new();
+        let _ = writeln!(out, "name: {}", scalar(&self.name));
+        let _ = writeln!(out, "cores:");
+        for (processor, name, kind) in &cores {
+            let _ = writeln!(out, "- name: {}", scalar(name));
+            let _ = writeln!(out, "  type: {}", kind);
+            let _ = writeln!(out, "  core_access_options: !Arm");
+            match processor.ap {
+                AccessPort::Index(index) => {
+                    let _ = writeln!(out, "    ap: !v1 {}", index);
+                }
+                AccessPort::Address(address) => {
+                    let _ = writeln!(out, "    ap: !v2 {:#x}", address);
+                }
+            }
+            if let Some(address) = processor.address {
+                let _ = writeln!(out, "    debug_base: {:#x}", address);
+            }
+        }
+
+        let _ = writeln!(out, "memory_map:");
+        for memory in self.memory_map() {
+            let kind = if memory.is_flash() {
+                "Nvm"
+            } else if memory.access.write {
+                "Ram"
+            } else {
+                "Generic"
+            };
+            let _ = writeln!(out, "- !{}", kind);
+            let _ = writeln!(out, "  name: {}", scalar(&memory.name));
+            let _ = writeln!(out, "  range:");
+            let _ = writeln!(out, "    start: {:#x}", memory.start);
+            let _ = writeln!(out, "    end: {:#x}", memory.end());
+            let _ = writeln!(out, "  cores:");
+            for (_, name, _) in cores
+                .iter()
+                .filter(|(p, _, _)| memory.applies_to_processor(p))
+            {
+                let _ = writeln!(out, "  - {}", scalar(name));
+            }
+            if memory.startup && kind != "Generic" {
+                let _ = writeln!(out, "  is_boot_memory: true");
+            }
+        }
+
+        let _ = writeln!(out, "flash_algorithms:");
+        for file in self.algorithm_files() {
+            let _ = writeln!(out, "- {}", scalar(&algorithm_name(file)));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::pdsc::Devices;
+    use crate::utils::prelude::*;
+
+    #[test]
+    fn stm32h745_golden() {
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="STM32H7 Series" Dvendor="STMicroelectronics:13">
+                <processor Pname="CM7" Dcore="Cortex-M7" Dfpu="DP_FPU"/>
+                <processor Pname="CM4" Dcore="Cortex-M4" Dfpu="SP_FPU"/>
+                <debug Pname="CM7" __ap="0"/>
+                <debug Pname="CM4" __ap="3"/>
+                <memory name="FLASH" access="rx" start="0x08000000" size="0x100000" startup="1"/>
+                <memory name="FLASH_CM4" Pname="CM4" access="rx" start="0x08100000" size="0x100000" startup="1"/>
+                <memory name="DTCM" Pname="CM7" access="rw" start="0x20000000" size="0x20000"/>
+                <memory name="SRAM1" access="rwx" start="0x30000000" size="0x20000"/>
+                <memory name="OTP" access="r" start="0x1FF0F000" size="0x400"/>
+                <memory name="PERIPH" access="rwp" start="0x40000000" size="0x20000000"/>
+                <algorithm name="CMSIS\Flash\STM32H7x_2048.FLM" start="0x08000000" size="0x200000" default="1"/>
+                <device Dname="STM32H745ZITx"/>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        assert_eq!(
+            devices.0["STM32H745ZITx"].to_probe_rs_yaml(),
+            include_str!("testdata/stm32h745.yaml")
+        );
+    }
+
+    #[test]
+    fn yaml_scalars() {
+        assert_eq!(scalar("STM32F407VGTx"), "STM32F407VGTx");
+        assert_eq!(scalar("Flash: A"), "\"Flash: A\"");
+        assert_eq!(scalar("-x"), "\"-x\"");
+        assert_eq!(scalar(""), "\"\"");
+        assert_eq!(
+            algorithm_name(std::path::Path::new("Flash/STM32F4xx 1024.FLM")),
+            "stm32f4xx_1024"
+        );
+    }
+}
@@ -0,0 +1,242 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::Serialize;
+use svd_parser::svd::{self, MaybeArray, PeripheralInfo};
+
+use super::validate::resolve;
+use super::Device;
+use anyhow::{format_err, Error};
+
+/// A peripheral of a device, as declared in its CMSIS-SVD file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Peripheral {
+    pub name: String,
+    pub base_address: u64,
+    pub group_name: Option<String>,
+    pub description: Option<String>,
+    /// The peripheral this one is a copy of, at a different base address.
+    pub derived_from: Option<String>,
+}
+
+impl Peripheral {
+    fn from_svd(info: &PeripheralInfo, derived_from: Option<String>) -> Self {
+        Peripheral {
+            name: info.name.clone(),
+            base_address: info.base_address,
+            group_name: info.group_name.clone(),
+            description: info
+                .description
+                .as_ref()
+                .map(|d| d.split_whitespace().collect::<Vec<_>>().join(" ")),
+            derived_from,
+        }
+    }
+}
+
+/// The peripherals of the SVD document `xml`, with `dim` arrays expanded into one peripheral
+/// per element and derived peripherals filled in from the peripheral they derive from.
+fn parse_peripherals(xml: &str) -> Result<Vec<Peripheral>, Error> {
+    let device = svd_parser::parse(xml)?;
+    // Expansion resolves `derivedFrom` and drops it, so note it by expanded name first.
+    let mut derived_from: HashMap<String, String> = HashMap::new();
+    for peripheral in &device.peripherals {
+        let Some(base) = &peripheral.derived_from else {
+            continue;
+        };
+        match peripheral {
+            MaybeArray::Single(info) => {
+                derived_from.insert(info.name.clone(), base.clone());
+            }
+            MaybeArray::Array(info, dim) => {
+                for element in svd::peripheral::expand(info, dim) {
+                    derived_from.insert(element.name, base.clone());
+                }
+            }
+        }
+    }
+    let device = svd_parser::expand(&device)?;
+    Ok(device
+        .peripherals
+        .iter()
+        .map(|p| Peripheral::from_svd(p, derived_from.remove(&p.name)))
+        .collect())
+}
+
+impl Device {
+    /// The peripherals declared in the SVD file of this device, sorted by base address.
+    ///
+    /// The SVD file is the first one referenced by a processor, resolved against `pack_root`,
+    /// the directory the pack was extracted to. An SVD path leading out of `pack_root` is an
+    /// error rather than a file read from elsewhere on disk.
+    pub fn load_peripherals(&self, pack_root: &Path) -> Result<Vec<Peripheral>, Error> {
+        let svd = self
+            .processors
+            .iter()
+            .find_map(|p| p.svd.as_deref())
+            .ok_or_else(|| format_err!("{} has no SVD file", self.name))?;
+        let mut peripherals = resolve(pack_root, Path::new(svd))
+            .and_then(|path| Ok(std::fs::read_to_string(path)?))
+            .and_then(|xml| parse_peripherals(&xml))
+            .map_err(|e| format_err!("{}: could not read SVD file {}: {}", self.name, svd, e))?;
+        peripherals.sort_by_key(|p| p.base_address);
+        Ok(peripherals)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::pdsc::Devices;
+    use crate::utils::FromElem;
+
+    const SVD: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+        <device schemaVersion="1.3">
+          <name>Device</name>
+          <peripherals>
+            <peripheral>
+              <name>USART1</name>
+              <description>Universal synchronous
+                asynchronous receiver transmitter</description>
+              <groupName>USART</groupName>
+              <baseAddress>0x40011000</baseAddress>
+            </peripheral>
+            <peripheral derivedFrom="USART1">
+              <name>USART2</name>
+              <baseAddress>1073759232</baseAddress>
+            </peripheral>
+            <peripheral>
+              <name>GPIOA</name>
+              <baseAddress>#1000000000000100000000000000000</baseAddress>
+            </peripheral>
+          </peripherals>
+        </device>"#;
+
+    #[test]
+    fn svd_arrays_and_clusters() {
+        let peripherals = parse_peripherals(
+            r#"<device schemaVersion="1.3">
+              <name>Device</name>
+              <peripherals>
+                <peripheral>
+                  <dim>2</dim>
+                  <dimIncrement>0x400</dimIncrement>
+                  <name>TIM%s</name>
+                  <groupName>TIM</groupName>
+                  <baseAddress>0x40000000</baseAddress>
+                  <registers>
+                    <cluster>
+                      <dim>4</dim>
+                      <dimIncrement>0x8</dimIncrement>
+                      <name>CH[%s]</name>
+                      <addressOffset>0x20</addressOffset>
+                      <register>
+                        <name>CCR</name>
+                        <addressOffset>0x0</addressOffset>
+                        <size>32</size>
+                      </register>
+                      <register derivedFrom="CCR">
+                        <name>CCMR</name>
+                        <addressOffset>0x4</addressOffset>
+                      </register>
+                    </cluster>
+                  </registers>
+                </peripheral>
+                <peripheral derivedFrom="TIM0">
+                  <name>LPTIM</name>
+                  <baseAddress>0x40007C00</baseAddress>
+                </peripheral>
+              </peripherals>
+            </device>"#,
+        )
+        .unwrap();
+        let names: Vec<(&str, u64)> = peripherals
+            .iter()
+            .map(|p| (p.name.as_str(), p.base_address))
+            .collect();
+        assert_eq!(
+            names,
+            [
+                ("TIM0", 0x4000_0000),
+                ("TIM1", 0x4000_0400),
+                ("LPTIM", 0x4000_7C00)
+            ]
+        );
+        assert_eq!(peripherals[2].derived_from.as_deref(), Some("TIM0"));
+        assert_eq!(peripherals[2].group_name.as_deref(), Some("TIM"));
+        assert_eq!(peripherals[1].derived_from, None);
+    }
+
+    #[test]
+    fn peripherals_from_svd() {
+        let root = std::env::temp_dir().join(format!("cmsis-pack-svd-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("SVD")).unwrap();
+        std::fs::write(root.join("SVD/Device.svd"), SVD).unwrap();
+
+        let devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="Family">
+                <processor Dcore="Cortex-M4"/>
+                <device Dname="WithSvd">
+                  <debug svd="SVD\Device.svd"/>
+                </device>
+                <device Dname="WithoutSvd"/>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        let peripherals = devices.0["WithSvd"].load_peripherals(&root);
+        let without = devices.0["WithoutSvd"].load_peripherals(&root);
+        std::fs::remove_dir_all(&root).unwrap();
+
+        let peripherals = peripherals.unwrap();
+        let names: Vec<&str> = peripherals.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["USART2", "USART1", "GPIOA"]);
+        assert_eq!(peripherals[2].base_address, 0x4002_0000);
+        let usart2 = &peripherals[0];
+        assert_eq!(usart2.base_address, 0x4000_4400);
+        assert_eq!(usart2.group_name.as_deref(), Some("USART"));
+        assert_eq!(
+            usart2.description.as_deref(),
+            Some("Universal synchronous asynchronous receiver transmitter")
+        );
+        assert!(without.is_err());
+    }
+
+    #[test]
+    fn svd_outside_the_pack() {
+        let dir = std::env::temp_dir().join(format!("cmsis-pack-svd-out-{}", std::process::id()));
+        let root = dir.join("pack");
+        std::fs::create_dir_all(&root).unwrap();
+        let outside = dir.join("Outside.svd");
+        std::fs::write(&outside, SVD).unwrap();
+
+        let devices = Devices::from_string(&format!(
+            r#"<devices>
+              <family Dfamily="Family">
+                <processor Dcore="Cortex-M4"/>
+                <device Dname="Relative">
+                  <debug svd="..\Outside.svd"/>
+                </device>
+                <device Dname="Absolute">
+                  <debug svd="{}"/>
+                </device>
+              </family>
+            </devices>"#,
+            outside.display()
+        ))
+        .unwrap();
+        let relative = devices.0["Relative"].load_peripherals(&root);
+        let absolute = devices.0["Absolute"].load_peripherals(&root);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(relative
+            .unwrap_err()
+            .to_string()
+            .contains("outside of the pack"));
+        assert!(absolute
+            .unwrap_err()
+            .to_string()
+            .contains("not relative to the pack"));
+    }
+}
@@ -0,0 +1,505 @@
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::{format_err, Error};
+
+use super::{Device, Memories, Memory};
+
+/// A likely authoring mistake found when validating a parsed [`Device`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationIssue {
+    /// No memory region visible to the processor is marked `startup`.
+    NoStartupMemory { processor: Option<String> },
+    /// More than one memory region visible to the processor is marked `startup`.
+    MultipleStartupMemories {
+        processor: Option<String>,
+        regions: Vec<String>,
+    },
+    /// The RAM a flash algorithm runs from, `RAMstart..RAMstart + RAMsize`, overlaps the range
+    /// the algorithm programs; running it would overwrite itself.
+    AlgoRamOverlapsTarget {
+        algorithm: PathBuf,
+        ram_start: u64,
+        ram_size: u64,
+    },
+    /// The region is scoped to a `Pname` that none of the device's processors have, so no
+    /// processor can see it.
+    UnreferencedMemoryPname { region: String, pname: String },
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ValidationIssue::NoStartupMemory { processor } => write!(
+                f,
+                "no startup memory for processor {}",
+                processor.as_deref().unwrap_or("<unnamed>")
+            ),
+            ValidationIssue::MultipleStartupMemories { processor, regions } => write!(
+                f,
+                "multiple startup memories for processor {}: {}",
+                processor.as_deref().unwrap_or("<unnamed>"),
+                regions.join(", ")
+            ),
+            ValidationIssue::AlgoRamOverlapsTarget {
+                algorithm,
+                ram_start,
+                ram_size,
+            } => write!(
+                f,
+                "RAM {:#010x}..{:#010x} of algorithm {} overlaps the flash it programs",
+                ram_start,
+                ram_start.saturating_add(*ram_size),
+                algorithm.display()
+            ),
+            ValidationIssue::UnreferencedMemoryPname { region, pname } => write!(
+                f,
+                "memory {} is scoped to unknown processor {}",
+                region, pname
+            ),
+        }
+    }
+}
+
+/// Two memory regions of a [`Device`] that share addresses, see
+/// [`Device::validate_memory_layout`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryOverlap {
+    /// The key in [`Memories`] of the lower addressed region.
+    pub first: String,
+    /// The key of the other region.
+    pub second: String,
+    /// The shared addresses, `start..end`.
+    pub start: u64,
+    pub end: u64,
+}
+
+impl fmt::Display for MemoryOverlap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "memory {} overlaps {} at {:#010x}..{:#010x}",
+            self.first, self.second, self.start, self.end
+        )
+    }
+}
+
+/// A file referenced by a [`Device`] that does not exist in the extracted pack.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MissingFile {
+    /// A flash algorithm `name`.
+    Algorithm(PathBuf),
+    /// A processor's `svd` file.
+    Svd(PathBuf),
+}
+
+impl fmt::Display for MissingFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MissingFile::Algorithm(path) => write!(f, "missing algorithm {}", path.display()),
+            MissingFile::Svd(path) => write!(f, "missing SVD file {}", path.display()),
+        }
+    }
+}
+
+/// Resolves a pack relative path, which may use `\` as a separator, against `pack_root`.
+///
+/// `.` and `..` segments are resolved without looking at the file system. A path that leads
+/// out of `pack_root`, or isn't relative to it at all, is an error, so a pack can't refer to
+/// files elsewhere on disk.
+pub(super) fn resolve(pack_root: &Path, file: &Path) -> Result<PathBuf, Error> {
+    let written = file.to_string_lossy().replace('\\', "/");
+    let mut resolved = PathBuf::new();
+    for component in Path::new(&written).components() {
+        match component {
+            Component::Normal(part) => resolved.push(part),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !resolved.pop() {
+                    return Err(format_err!("{} is outside of the pack", file.display()));
+                }
+            }
+            Component::RootDir | Component::Prefix(_) => {
+                return Err(format_err!(
+                    "{} is not relative to the pack",
+                    file.display()
+                ))
+            }
+        }
+    }
+    if resolved.as_os_str().is_empty() {
+        return Err(format_err!("{} does not name a file", file.display()));
+    }
+    Ok(pack_root.join(resolved))
+}
+
+impl Memories {
+    /// Checks that exactly one region is marked `startup` for each processor.
+    ///
+    /// The processors considered are the distinct `Pname`s the regions are scoped to, or a single
+    /// unnamed processor if no region is scoped. Use [`Device::validate`] to check against the
+    /// processors a device actually declares.
+    pub fn check_startup(&self) -> Vec<ValidationIssue> {
+        let mut processors: Vec<Option<&str>> = self
+            .0
+            .values()
+            .filter_map(|m| m.p_name.as_deref())
+            .map(Some)
+            .collect();
+        processors.sort();
+        processors.dedup();
+        if processors.is_empty() {
+            processors.push(None);
+        }
+        let issues: Vec<ValidationIssue> = processors
+            .into_iter()
+            .filter_map(|processor| {
+                self.startup_issue(processor, |m: &Memory| m.applies_to(processor))
+            })
+            .collect();
+        for issue in &issues {
+            log::warn!("{}", issue);
+        }
+        issues
+    }
+
+    /// The issue, if any, with the `startup` regions of the processor named `processor`, which
+    /// sees the regions for which `visible` returns true.
+    fn startup_issue<F>(&self, processor: Option<&str>, visible: F) -> Option<ValidationIssue>
+    where
+        F: Fn(&Memory) -> bool,
+    {
+        let mut regions: Vec<String> = self
+            .0
+            .iter()
+            .filter(|(_, m)| m.startup && visible(m))
+            .map(|(name, _)| name.clone())
+            .collect();
+        regions.sort();
+        match regions.len() {
+            0 => Some(ValidationIssue::NoStartupMemory {
+                processor: processor.map(String::from),
+            }),
+            1 => None,
+            _ => Some(ValidationIssue::MultipleStartupMemories {
+                processor: processor.map(String::from),
+                regions,
+            }),
+        }
+    }
+}
+
+impl Device {
+    /// Checks this device for common pack authoring mistakes, logging a warning for each.
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        let mut issues: Vec<ValidationIssue> = Vec::new();
+        for processor in &self.processors {
+            let issue = self.memories.startup_issue(processor.name.as_deref(), |m| {
+                m.applies_to_processor(processor)
+            });
+            // The units of a processor share its name, so report each issue once.
+            if let Some(issue) = issue.filter(|issue| !issues.contains(issue)) {
+                issues.push(issue);
+            }
+        }
+        let mut unreferenced: Vec<(&String, &String)> = self
+            .memories
+            .0
+            .iter()
+            .filter_map(|(region, m)| Some((region, m.p_name.as_ref()?)))
+            .filter(|(_, pname)| {
+                !self.processors.iter().any(|p| {
+                    p.name
+                        .as_ref()
+                        .is_some_and(|n| n.eq_ignore_ascii_case(pname))
+                })
+            })
+            .collect();
+        unreferenced.sort();
+        issues.extend(unreferenced.into_iter().map(|(region, pname)| {
+            ValidationIssue::UnreferencedMemoryPname {
+                region: region.clone(),
+                pname: pname.clone(),
+            }
+        }));
+        for algorithm in &self.algorithms {
+            let (Some(ram_start), Some(ram_size)) = (algorithm.ram_start, algorithm.ram_size)
+            else {
+                continue;
+            };
+            let ram_end = ram_start.saturating_add(ram_size);
+            let target_end = algorithm.start.saturating_add(algorithm.size);
+            if ram_start < target_end && algorithm.start < ram_end {
+                issues.push(ValidationIssue::AlgoRamOverlapsTarget {
+                    algorithm: algorithm.file_name.clone(),
+                    ram_start,
+                    ram_size,
+                });
+            }
+        }
+        for issue in &issues {
+            log::warn!("{}: {}", self.name, issue);
+        }
+        issues
+    }
+
+    /// Checks that no two memory regions of this device share addresses, logging a warning for
+    /// each pair that does.
+    ///
+    /// Peripheral regions and empty regions are left out, as are pairs scoped to different
+    /// processors, which may map different memories at the same address. Regions that merely
+    /// touch, one ending where the other starts, don't overlap.
+    pub fn validate_memory_layout(&self) -> Result<(), Vec<MemoryOverlap>> {
+        let mut regions: Vec<(&String, &Memory)> = self
+            .memories
+            .0
+            .iter()
+            .filter(|(_, m)| !m.access.peripheral && m.size != 0)
+            .collect();
+        regions.sort_by_key(|(name, m)| (m.start, *name));
+        let mut overlaps = Vec::new();
+        for (idx, (first, a)) in regions.iter().enumerate() {
+            for (second, b) in &regions[idx + 1..] {
+                let shared = a.p_name.is_none() || b.p_name.is_none() || a.p_name == b.p_name;
+                if shared && b.start < a.end() {
+                    overlaps.push(MemoryOverlap {
+                        first: (*first).clone(),
+                        second: (*second).clone(),
+                        start: b.start,
+                        end: a.end().min(b.end()),
+                    });
+                }
+            }
+        }
+        for overlap in &overlaps {
+            log::warn!("{}: {}", self.name, overlap);
+        }
+        if overlaps.is_empty() {
+            Ok(())
+        } else {
+            Err(overlaps)
+        }
+    }
+
+    /// Checks that every algorithm and SVD file this device references exists below
+    /// `pack_root`, the directory the pack was extracted to.
+    ///
+    /// Each missing file is reported once, with the path as written in the pack. A path leading
+    /// out of `pack_root` is reported as missing without looking for it, even if a file exists
+    /// there.
+    pub fn check_files_exist(&self, pack_root: &Path) -> Vec<MissingFile> {
+        let mut missing = Vec::new();
+        for file in self.algorithm_files() {
+            if !resolve(pack_root, file).is_ok_and(|path| path.is_file()) {
+                missing.push(MissingFile::Algorithm(file.to_path_buf()));
+            }
+        }
+        for svd in self.processors.iter().filter_map(|p| p.svd.as_deref()) {
+            let svd = Path::new(svd);
+            if !resolve(pack_root, svd).is_ok_and(|path| path.is_file()) {
+                let file = MissingFile::Svd(svd.to_path_buf());
+                if !missing.contains(&file) {
+                    missing.push(file);
+                }
+            }
+        }
+        for file in &missing {
+            log::warn!("{}: {}", self.name, file);
+        }
+        missing
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::pdsc::Devices;
+    use crate::utils::prelude::*;
+
+    fn device(memories: &str) -> Device {
+        let xml = format!(
+            r#"<devices>
+              <family Dfamily="Family">
+                <processor Pname="cm7" Dcore="Cortex-M7"/>
+                <processor Pname="cm4" Dcore="Cortex-M4"/>
+                {}
+                <device Dname="Device"/>
+              </family>
+            </devices>"#,
+            memories
+        );
+        let mut devices = Devices::from_string(&xml).unwrap();
+        devices.0.remove("Device").unwrap()
+    }
+
+    #[test]
+    fn files_outside_the_pack_are_missing() {
+        let dir = std::env::temp_dir().join(format!("cmsis-pack-escape-{}", std::process::id()));
+        let root = dir.join("pack");
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(dir.join("Escape.FLM"), b"").unwrap();
+        std::fs::write(dir.join("Escape.svd"), b"").unwrap();
+        let svd = dir.join("Escape.svd");
+
+        let device = device(&format!(
+            r#"<debug svd="{}"/>
+               <algorithm name="..\Escape.FLM" start="0x08000000" size="0x100000"/>"#,
+            svd.display()
+        ));
+        let missing = device.check_files_exist(&root);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            missing,
+            vec![
+                MissingFile::Algorithm(PathBuf::from("../Escape.FLM")),
+                MissingFile::Svd(svd),
+            ]
+        );
+    }
+
+    #[test]
+    fn referenced_files_exist() {
+        let root = std::env::temp_dir().join(format!("cmsis-pack-files-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("Flash")).unwrap();
+        std::fs::write(root.join("Flash/Main.FLM"), b"").unwrap();
+        std::fs::write(root.join("Device.svd"), b"").unwrap();
+
+        let mut devices = Devices::from_string(
+            r#"<devices>
+              <family Dfamily="Family">
+                <processor Pname="cm7" Dcore="Cortex-M7"/>
+                <processor Pname="cm4" Dcore="Cortex-M4"/>
+                <debug svd="Device.svd"/>
+                <algorithm name="Flash\Main.FLM" start="0x08000000" size="0x100000"/>
+                <algorithm name="Flash/OTP.FLM" start="0x1FFF7800" size="0x200"/>
+                <device Dname="Present"/>
+                <device Dname="Missing">
+                  <debug svd="SVD/Missing.svd"/>
+                </device>
+              </family>
+            </devices>"#,
+        )
+        .unwrap();
+        let present = devices.0.remove("Present").unwrap();
+        let missing = devices.0.remove("Missing").unwrap();
+        let present = present.check_files_exist(&root);
+        let missing = missing.check_files_exist(&root);
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(
+            present,
+            vec![MissingFile::Algorithm(PathBuf::from("Flash/OTP.FLM"))]
+        );
+        assert_eq!(
+            missing,
+            vec![
+                MissingFile::Algorithm(PathBuf::from("Flash/OTP.FLM")),
+                MissingFile::Svd(PathBuf::from("SVD/Missing.svd")),
+            ]
+        );
+    }
+
+    #[test]
+    fn algorithm_ram_inside_target() {
+        let device = device(
+            r#"<memory id="FLASH" start="0x08000000" size="0x100000" startup="1"/>
+               <algorithm name="Flash/Good.FLM" start="0x08000000" size="0x100000"
+                          RAMstart="0x20000000" RAMsize="0x1000"/>
+               <algorithm name="Flash/Bad.FLM" start="0x08000000" size="0x100000"
+                          RAMstart="0x080FF000" RAMsize="0x2000"/>
+               <algorithm name="Flash/Adjacent.FLM" start="0x08000000" size="0x100000"
+                          RAMstart="0x08100000" RAMsize="0x1000"/>"#,
+        );
+        assert_eq!(
+            device.validate(),
+            vec![ValidationIssue::AlgoRamOverlapsTarget {
+                algorithm: PathBuf::from("Flash/Bad.FLM"),
+                ram_start: 0x080F_F000,
+                ram_size: 0x2000,
+            }]
+        );
+    }
+
+    #[test]
+    fn memory_scoped_to_unknown_processor() {
+        let device = device(
+            r#"<memory id="FLASH" start="0x08000000" size="0x100000" startup="1"/>
+               <memory id="DTCM" Pname="cm77" start="0x20000000" size="0x20000"/>"#,
+        );
+        assert_eq!(
+            device.validate(),
+            vec![ValidationIssue::UnreferencedMemoryPname {
+                region: "DTCM".into(),
+                pname: "cm77".into(),
+            }]
+        );
+    }
+
+    #[test]
+    fn overlapping_memories() {
+        let overlapping = device(
+            r#"<memory id="FLASH" start="0x08000000" size="0x100000" startup="1"/>
+               <memory id="FLASH_BANK2" start="0x08080000" size="0x100000"/>
+               <memory id="SRAM1" access="rw" start="0x20000000" size="0x10000"/>
+               <memory id="SRAM2" access="rw" start="0x20010000" size="0x10000"/>
+               <memory id="EMPTY" access="rw" start="0x20008000" size="0"/>
+               <memory id="PERIPH" access="rwp" start="0x20000000" size="0x100000"/>
+               <memory id="ITCM" Pname="cm7" access="rw" start="0x00000000" size="0x10000"/>
+               <memory id="CM4_RAM" Pname="cm4" access="rw" start="0x00000000" size="0x8000"/>"#,
+        );
+        assert_eq!(
+            overlapping.validate_memory_layout(),
+            Err(vec![MemoryOverlap {
+                first: "FLASH".into(),
+                second: "FLASH_BANK2".into(),
+                start: 0x0808_0000,
+                end: 0x0810_0000,
+            }])
+        );
+
+        let touching = device(
+            r#"<memory id="FLASH" start="0x08000000" size="0x100000" startup="1"/>
+               <memory id="FLASH_BANK2" start="0x08100000" size="0x100000"/>"#,
+        );
+        assert_eq!(touching.validate_memory_layout(), Ok(()));
+    }
+
+    #[test]
+    fn one_startup_region_per_processor() {
+        let device = device(
+            r#"<memory id="FLASH" start="0x08000000" size="0x100000" startup="1"/>
+               <memory id="RAM" start="0x20000000" size="0x20000"/>"#,
+        );
+        assert_eq!(device.validate(), vec![]);
+        assert_eq!(device.memories.check_startup(), vec![]);
+    }
+
+    #[test]
+    fn missing_startup_region() {
+        let device = device(
+            r#"<memory id="FLASH" Pname="cm7" start="0x08000000" size="0x100000" startup="1"/>
+               <memory id="RAM" start="0x20000000" size="0x20000"/>"#,
+        );
+        assert_eq!(
+            device.validate(),
+            vec![ValidationIssue::NoStartupMemory {
+                processor: Some("cm4".into())
+            }]
+        );
+    }
+
+    #[test]
+    fn multiple_startup_regions() {
+        let device = device(
+            r#"<memory id="FLASH" start="0x08000000" size="0x100000" startup="1"/>
+               <memory id="FLASH_CM4" Pname="cm4" start="0x08100000" size="0x100000" startup="1"/>"#,
+        );
+        let expected = vec![ValidationIssue::MultipleStartupMemories {
+            processor: Some("cm4".into()),
+            regions: vec!["FLASH".into(), "FLASH_CM4".into()],
+        }];
+        assert_eq!(device.validate(), expected);
+        assert_eq!(device.memories.check_startup(), expected);
+    }
+}
@@ -11,7 +11,7 @@ use tokio::task::JoinHandle;
 use tokio::time::{sleep, Duration};
 
 use crate::pack_index::{PdscRef, Vidx};
-use crate::pdsc::Package;
+use crate::pdsc::{Pack, PackInfo};
 use crate::utils::parse::FromElem;
 use futures::StreamExt;
 use std::collections::HashMap;
@@ -66,33 +66,37 @@ impl IntoDownload for PdscRef {
     }
 }
 
-impl IntoDownload for &Package {
+impl IntoDownload for &Pack {
     fn into_uri(&self) -> Result<Url, Error> {
-        let Package {
+        let PackInfo {
             name,
             vendor,
-            url,
             releases,
             ..
-        } = *self;
-        let version: &str = releases.latest_release().version.as_ref();
-        let uri = if url.ends_with('/') {
-            format!("{}{}.{}.{}.pack", url, vendor, name, version)
-        } else {
-            format!("{}/{}.{}.{}.pack", url, vendor, name, version)
-        }
-        .parse()?;
+        } = &self.info;
+        let version: &str = releases
+            .latest_release()
+            .ok_or_else(|| anyhow!("{}.{} has no releases", vendor, name))?
+            .version
+            .as_ref();
+        let uri = self
+            .info
+            .download_url(version)
+            .ok_or_else(|| anyhow!("{}.{} has no url", vendor, name))?
+            .parse()?;
         Ok(uri)
     }
 
     fn into_fd<D: DownloadConfig>(&self, config: &D) -> PathBuf {
-        let Package {
+        let PackInfo {
             name,
             vendor,
             releases,
             ..
-        } = *self;
-        let version: &str = releases.latest_release().version.as_ref();
+        } = &self.info;
+        let version: &str = releases
+            .latest_release()
+            .map_or("unreleased", |r| r.version.as_ref());
         let mut filename = config.pack_store();
         filename.push(Path::new(vendor));
         filename.push(Path::new(name));
@@ -259,11 +263,7 @@ where
                                 match res {
                                     Ok(r) => (host, r.0, Some(r.1)),
                                     Err(err) => {
-                                        log::warn!(
-                                            "Download of {} failed: {}",
-                                            source.to_string(),
-                                            err
-                                        );
+                                        log::warn!("Download of {} failed: {}", source, err);
                                         (host, 0, None)
                                     }
                                 }
@@ -341,7 +341,7 @@ where
         pdscs.dedup_by_key(pdsc_url);
         log::info!("Found {} Pdsc entries", pdscs.len());
 
-        Ok(self.download_iterator(pdscs.into_iter()).await)
+        Ok(self.download_iterator(pdscs).await)
     }
 
     pub(crate) async fn download_vidx<I: Into<String>>(
@@ -2,7 +2,7 @@ use anyhow::Error;
 use std::path::PathBuf;
 use tokio::runtime;
 
-use crate::pdsc::Package;
+use crate::pdsc::Pack;
 
 mod download;
 
@@ -29,7 +29,7 @@ where
 /// Flatten a list of Vidx Urls into a list of updated CMSIS packs
 pub fn install<'a, I, P, D>(config: &'a D, pdsc_list: I, progress: P) -> Result<Vec<PathBuf>>
 where
-    I: IntoIterator<Item = &'a Package>,
+    I: IntoIterator<Item = &'a Pack>,
     P: DownloadProgress + 'a,
     D: DownloadConfig,
 {
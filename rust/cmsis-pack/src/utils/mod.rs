@@ -1,9 +1,90 @@
 pub(crate) mod parse;
 pub(crate) mod prelude;
 
-pub use self::parse::FromElem;
+pub use self::parse::{Diagnostic, Diagnostics, FromElem, LogDiagnostics, ParseOptions, PathStyle};
 
+use std::cmp::Ordering;
 use std::fmt::Display;
+use std::hash::Hasher;
+
+/// A 64 bit FNV-1a hasher. Unlike the standard library's `DefaultHasher`, its output is
+/// unseeded and specified, so the same input hashes the same on every run.
+pub(crate) struct StableHasher(u64);
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        StableHasher(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for StableHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Orders pack versions such as `1.2.0` or `2.0.0-rc1` the way semantic versioning does:
+/// numerically by component, with a missing component read as zero and a pre-release ordered
+/// before its release. Build metadata after `+` is ignored.
+pub(crate) fn compare_versions(left: &str, right: &str) -> Ordering {
+    fn split(version: &str) -> (&str, Option<&str>) {
+        let version = version.trim();
+        let version = version.split_once('+').map_or(version, |(v, _)| v);
+        match version.split_once('-') {
+            Some((release, pre)) => (release, Some(pre)),
+            None => (version, None),
+        }
+    }
+    fn compare_component(left: &str, right: &str) -> Ordering {
+        match (left.parse::<u64>(), right.parse::<u64>()) {
+            (Ok(l), Ok(r)) => l.cmp(&r),
+            (Ok(_), Err(_)) => Ordering::Less,
+            (Err(_), Ok(_)) => Ordering::Greater,
+            (Err(_), Err(_)) => left.cmp(right),
+        }
+    }
+
+    let (left_release, left_pre) = split(left);
+    let (right_release, right_pre) = split(right);
+    let mut left_parts = left_release.split('.');
+    let mut right_parts = right_release.split('.');
+    loop {
+        let ordering = match (left_parts.next(), right_parts.next()) {
+            (None, None) => break,
+            (l, r) => compare_component(l.unwrap_or("0"), r.unwrap_or("0")),
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    match (left_pre, right_pre) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (Some(l), Some(r)) => {
+            let mut left_parts = l.split('.');
+            let mut right_parts = r.split('.');
+            loop {
+                let ordering = match (left_parts.next(), right_parts.next()) {
+                    (None, None) => return Ordering::Equal,
+                    (None, Some(_)) => return Ordering::Less,
+                    (Some(_), None) => return Ordering::Greater,
+                    (Some(l), Some(r)) => compare_component(l, r),
+                };
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+        }
+    }
+}
 
 pub trait ResultLogExt<T, E> {
     fn ok_warn(self) -> Option<T>;
@@ -33,3 +114,29 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn stable_hasher_is_fnv1a() {
+        let mut hasher = StableHasher::default();
+        hasher.write(b"a");
+        assert_eq!(hasher.finish(), 0xaf63_dc4c_8601_ec8c);
+    }
+
+    #[test]
+    fn versions_compare_numerically() {
+        assert_eq!(compare_versions("1.3.0", "1.2.0"), Ordering::Greater);
+        assert_eq!(compare_versions("1.10.0", "1.9.0"), Ordering::Greater);
+        assert_eq!(compare_versions("1.2", "1.2.0"), Ordering::Equal);
+        assert_eq!(compare_versions("1.2.0+build5", "1.2.0"), Ordering::Equal);
+        assert_eq!(compare_versions("2.0.0-rc1", "2.0.0"), Ordering::Less);
+        assert_eq!(
+            compare_versions("2.0.0-rc.2", "2.0.0-rc.10"),
+            Ordering::Less
+        );
+        assert_eq!(compare_versions("2.0.0-rc1", "1.9.9"), Ordering::Greater);
+    }
+}
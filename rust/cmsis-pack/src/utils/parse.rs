@@ -1,6 +1,8 @@
+use std::collections::HashMap;
 use std::fmt::Display;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
+use std::ops::Range;
 use std::path::Path;
 use std::str::FromStr;
 
@@ -32,6 +34,46 @@ pub fn attr_parse_hex(from: &Node, name: &str) -> Result<u64, Error> {
         })
 }
 
+/// Parses a byte count or address, which packs write in hex, e.g. `0x40000`, or as a decimal
+/// with an optional `K`, `M` or `G` multiplier in either case, e.g. `256K`. A suffix on hex,
+/// e.g. `0x100K`, multiplies too. Otherwise this reads the number like [`attr_parse_hex`],
+/// except that a bare `0` is zero.
+pub fn attr_parse_size(from: &Node, name: &str) -> Result<u64, Error> {
+    let st = from
+        .attribute(name)
+        .ok_or_else(|| format_err!("{} not found in {} element", name, from.tag_name().name()))?;
+    let (number, multiplier) = match st.as_bytes().last() {
+        Some(b'k' | b'K') => (&st[..st.len() - 1], 1 << 10),
+        Some(b'm' | b'M') => (&st[..st.len() - 1], 1 << 20),
+        Some(b'g' | b'G') => (&st[..st.len() - 1], 1 << 30),
+        _ => (st, 1),
+    };
+    let value = if let Some(hex) = number.strip_prefix("0x") {
+        u64::from_str_radix(hex, 16)
+    } else if let Some(oct) = number.strip_prefix('0').filter(|oct| !oct.is_empty()) {
+        u64::from_str_radix(oct, 8)
+    } else {
+        number.parse::<u64>()
+    }
+    .map_err(|e| {
+        format_err!(
+            "invalid {} {:?} in {} element: {}",
+            name,
+            st,
+            from.tag_name().name(),
+            e
+        )
+    })?;
+    value.checked_mul(multiplier).ok_or_else(|| {
+        format_err!(
+            "{} {:?} in {} element is out of range",
+            name,
+            st,
+            from.tag_name().name()
+        )
+    })
+}
+
 pub fn attr_parse<T, E>(from: &Node, name: &str) -> Result<T, Error>
 where
     T: FromStr<Err = E>,
@@ -55,6 +97,123 @@ pub fn child_text(from: &Node, name: &str) -> Result<String, Error> {
     ))
 }
 
+/// Options controlling what is captured while parsing, see [`FromElem::from_string_with`].
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    /// Keep the attributes this crate doesn't model in the `extra_attributes` of devices,
+    /// processors and memories.
+    pub capture_extra: bool,
+    /// Collapse the `<variant>`s of a device whose names differ only in package and
+    /// temperature grade into one device, listing their names in `Device::variants`.
+    pub collapse_variants: bool,
+    /// How the pack relative paths of algorithms and SVD files are written.
+    pub path_style: PathStyle,
+}
+
+/// The separator used for pack relative paths, which packs write with either `\` or `/`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PathStyle {
+    /// Separate with `/`, the same on every platform.
+    #[default]
+    Posix,
+    /// Separate with the separator of the platform we run on.
+    Native,
+    /// Keep the path as written in the pack.
+    Raw,
+}
+
+impl PathStyle {
+    /// Rewrites the separators of the pack relative `path` in this style.
+    pub fn normalize(self, path: &str) -> String {
+        match self {
+            PathStyle::Posix => path.replace('\\', "/"),
+            PathStyle::Native => path.replace(['\\', '/'], std::path::MAIN_SEPARATOR_STR),
+            PathStyle::Raw => path.to_string(),
+        }
+    }
+}
+
+/// The attributes of `e` not in `known`, when `opts` asks for them to be captured.
+pub fn extra_attributes(e: &Node, known: &[&str], opts: &ParseOptions) -> HashMap<String, String> {
+    if !opts.capture_extra {
+        return HashMap::new();
+    }
+    e.attributes()
+        .filter(|attr| !known.contains(&attr.name()))
+        .map(|attr| (attr.name().to_string(), attr.value().to_string()))
+        .collect()
+}
+
+/// An element skipped while parsing, and why.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// The tag name of the skipped element, e.g. `algorithm`.
+    pub tag: String,
+    /// Where the element is in the document, as a byte range. A leading byte order mark is not
+    /// counted, and a document repaired by the `lenient` feature is counted after the repair.
+    pub range: Range<usize>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn new(e: &Node, message: impl Display) -> Self {
+        Diagnostic {
+            tag: e.tag_name().name().to_string(),
+            range: e.range(),
+            message: message.to_string(),
+        }
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{} at bytes {}..{}: {}",
+            self.tag, self.range.start, self.range.end, self.message
+        )
+    }
+}
+
+/// Receives the elements skipped while parsing, see [`FromElem::from_string_with_diagnostics`].
+pub trait Diagnostics {
+    fn report(&mut self, diagnostic: Diagnostic);
+}
+
+/// Logs each diagnostic, with the tag and byte range of its element, as a warning. Parsing
+/// reports here unless given another sink.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LogDiagnostics;
+
+impl Diagnostics for LogDiagnostics {
+    fn report(&mut self, diagnostic: Diagnostic) {
+        log::warn!("{}", diagnostic);
+    }
+}
+
+impl Diagnostics for Vec<Diagnostic> {
+    fn report(&mut self, diagnostic: Diagnostic) {
+        self.push(diagnostic);
+    }
+}
+
+pub trait ResultDiagnosticsExt<T> {
+    /// Like [`ResultLogExt::ok_warn`], but reports an error as a [`Diagnostic`] about `e`.
+    fn ok_report(self, e: &Node, diags: &mut dyn Diagnostics) -> Option<T>;
+}
+
+impl<T, E: Display> ResultDiagnosticsExt<T> for Result<T, E> {
+    fn ok_report(self, e: &Node, diags: &mut dyn Diagnostics) -> Option<T> {
+        match self {
+            Ok(x) => Some(x),
+            Err(err) => {
+                diags.report(Diagnostic::new(e, err));
+                None
+            }
+        }
+    }
+}
+
 pub fn assert_root_name(from: &Node, name: &str) -> Result<(), Error> {
     if from.tag_name().name() != name {
         Err(format_err!(
@@ -68,13 +227,169 @@ pub fn assert_root_name(from: &Node, name: &str) -> Result<(), Error> {
     }
 }
 
+/// Repairs the XML mistakes most often found in vendor packs: unescaped `&` characters and
+/// control characters that XML does not allow. Returns `None` if there was nothing to repair.
+#[cfg(feature = "lenient")]
+pub fn repair_xml(s: &str) -> Option<String> {
+    /// The longest entity or character reference looked for after a `&`. Looking no further
+    /// keeps repairing a document full of bare `&` linear in its length.
+    const MAX_REFERENCE_LEN: usize = 32;
+
+    fn is_reference(s: &str) -> bool {
+        let Some(end) = s
+            .char_indices()
+            .take(MAX_REFERENCE_LEN + 1)
+            .find(|(_, c)| !(c.is_alphanumeric() || matches!(c, '#' | '_' | '-' | '.' | ':')))
+            .map(|(idx, _)| idx)
+        else {
+            return false;
+        };
+        let name = &s[..end];
+        if !s[end..].starts_with(';') {
+            return false;
+        }
+        if let Some(hex) = name.strip_prefix("#x") {
+            !hex.is_empty() && hex.chars().all(|c| c.is_ascii_hexdigit())
+        } else if let Some(dec) = name.strip_prefix('#') {
+            !dec.is_empty() && dec.chars().all(|c| c.is_ascii_digit())
+        } else {
+            name.chars()
+                .next()
+                .is_some_and(|c| c.is_alphabetic() || c == '_')
+                && name
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | ':'))
+        }
+    }
+
+    let mut repaired = String::with_capacity(s.len());
+    let mut ampersands = Vec::new();
+    let mut controls = Vec::new();
+    for (idx, c) in s.char_indices() {
+        match c {
+            '&' if !is_reference(&s[idx + 1..]) => {
+                ampersands.push(idx);
+                repaired.push_str("&amp;");
+            }
+            '\t' | '\n' | '\r' => repaired.push(c),
+            c if (c as u32) < 0x20 => controls.push(idx),
+            c => repaired.push(c),
+        }
+    }
+    if ampersands.is_empty() && controls.is_empty() {
+        return None;
+    }
+    log::warn!(
+        "Repaired malformed XML: escaped {} bare '&' at byte offsets {:?}, removed {} control characters at byte offsets {:?}",
+        ampersands.len(),
+        ampersands,
+        controls.len(),
+        controls,
+    );
+    Some(repaired)
+}
+
+/// The deepest element nesting accepted. `roxmltree` recurses once per nested element, so
+/// hostile input could otherwise overflow the stack; real descriptions nest a handful of levels.
+const MAX_NESTING: usize = 256;
+
+/// Rejects documents nesting elements deeper than [`MAX_NESTING`]. Only tag delimiters are
+/// looked at; everything else is left for the parser to report.
+pub(crate) fn check_nesting(s: &str) -> Result<(), Error> {
+    let mut rest = s;
+    let mut depth = 0usize;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start..];
+        let terminator = if rest.starts_with("<!--") {
+            "-->"
+        } else if rest.starts_with("<![CDATA[") {
+            "]]>"
+        } else if rest.starts_with("<?") {
+            "?>"
+        } else if rest.starts_with("<!") {
+            ">"
+        } else {
+            let mut quote = None;
+            let Some(end) = rest.bytes().position(|b| match quote {
+                Some(q) => {
+                    if b == q {
+                        quote = None;
+                    }
+                    false
+                }
+                None if b == b'"' || b == b'\'' => {
+                    quote = Some(b);
+                    false
+                }
+                None => b == b'>',
+            }) else {
+                break;
+            };
+            let tag = &rest[..end];
+            if tag.starts_with("</") {
+                depth = depth.saturating_sub(1);
+            } else if !tag.ends_with('/') {
+                depth += 1;
+                if depth > MAX_NESTING {
+                    return Err(format_err!(
+                        "elements are nested deeper than {} levels",
+                        MAX_NESTING
+                    ));
+                }
+            }
+            rest = &rest[end + 1..];
+            continue;
+        };
+        match rest.find(terminator) {
+            Some(end) => rest = &rest[end + terminator.len()..],
+            None => break,
+        }
+    }
+    Ok(())
+}
+
 pub trait FromElem: Sized {
     fn from_elem(e: &Node) -> Result<Self, Error>;
 
+    /// Parse with `opts`. Elements that have no options to honor just use `from_elem`.
+    fn from_elem_with(e: &Node, _opts: &ParseOptions) -> Result<Self, Error> {
+        Self::from_elem(e)
+    }
+
+    /// Parse with `opts`, reporting the elements skipped along the way to `diags` instead of
+    /// logging them. Elements that report nothing just use `from_elem_with`.
+    fn from_elem_with_diagnostics(
+        e: &Node,
+        opts: &ParseOptions,
+        _diags: &mut dyn Diagnostics,
+    ) -> Result<Self, Error> {
+        Self::from_elem_with(e, opts)
+    }
+
     fn from_string(s: &str) -> Result<Self, Error> {
+        Self::from_string_with(s, &ParseOptions::default())
+    }
+
+    fn from_string_with(s: &str, opts: &ParseOptions) -> Result<Self, Error> {
+        Self::from_string_with_diagnostics(s, opts, &mut LogDiagnostics)
+    }
+
+    fn from_string_with_diagnostics(
+        s: &str,
+        opts: &ParseOptions,
+        diags: &mut dyn Diagnostics,
+    ) -> Result<Self, Error> {
+        let s = s.strip_prefix('\u{feff}').unwrap_or(s);
+        check_nesting(s)?;
+        #[cfg(feature = "lenient")]
+        if let Err(err) = roxmltree::Document::parse(s) {
+            let repaired = repair_xml(s).ok_or(err)?;
+            let doc = roxmltree::Document::parse(&repaired)?;
+            return Self::from_elem_with_diagnostics(&doc.root_element(), opts, diags);
+        }
         let doc = roxmltree::Document::parse(s)?;
         let root = doc.root_element();
-        Self::from_elem(&root)
+        Self::from_elem_with_diagnostics(&root, opts, diags)
     }
 
     fn from_reader<T: BufRead>(r: &mut T) -> Result<Self, Error> {
@@ -89,9 +404,254 @@ pub trait FromElem: Sized {
         Self::from_reader(&mut r)
     }
 
+    /// Parse from a memory mapped file, avoiding a copy of large files into a `String`.
+    #[cfg(feature = "mmap")]
+    fn from_mmap(p: &Path) -> Result<Self, Error> {
+        let f = File::open(p)?;
+        // Safety: the mapping is only read, and dropped before returning. As with any file
+        // mapping, the file must not be truncated by another process while it is parsed.
+        let map = unsafe { memmap2::Mmap::map(&f)? };
+        Self::from_string(std::str::from_utf8(&map)?)
+    }
+
     fn vec_from_children(clds: Children) -> Vec<Self> {
         clds.filter(|e| e.is_element())
             .flat_map(move |cld| Self::from_elem(&cld).ok_warn().into_iter())
             .collect()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::pdsc::Devices;
+
+    const DEVICES: &str = r#"<devices>
+          <family Dfamily="Family">
+            <processor Dcore="Cortex-M4"/>
+            <device Dname="Device"/>
+          </family>
+        </devices>"#;
+
+    #[test]
+    fn from_string_skips_byte_order_mark() {
+        let devices = Devices::from_string(&format!("\u{feff}{}", DEVICES)).unwrap();
+        assert!(devices.0.contains_key("Device"));
+    }
+
+    #[test]
+    fn sizes_with_suffixes() {
+        let size = |value: &str| {
+            let xml = format!(r#"<memory size="{}"/>"#, value);
+            let doc = roxmltree::Document::parse(&xml).unwrap();
+            attr_parse_size(&doc.root_element(), "size")
+        };
+        assert_eq!(size("0x1000").unwrap(), 0x1000);
+        assert_eq!(size("4K").unwrap(), 4 * 1024);
+        assert_eq!(size("1M").unwrap(), 1024 * 1024);
+        assert_eq!(size("2g").unwrap(), 2 << 30);
+        assert_eq!(size("0x10k").unwrap(), 16 * 1024);
+        assert_eq!(size("0").unwrap(), 0);
+        assert_eq!(size("4096").unwrap(), 4096);
+        assert!(size("12Q").is_err());
+        assert!(size("K").is_err());
+        assert!(size("0xFFFFFFFFFFFFG").is_err());
+    }
+
+    #[cfg(feature = "lenient")]
+    #[test]
+    fn repair_bare_ampersands_and_control_characters() {
+        assert_eq!(repair_xml("<a b=\"&amp; &#38; &#x26;\">&lt;</a>"), None);
+        assert_eq!(
+            repair_xml("<a b=\"R&D\">Tom & Jerry\u{1}\t</a>").as_deref(),
+            Some("<a b=\"R&amp;D\">Tom &amp; Jerry\t</a>")
+        );
+        // A `;` further on doesn't make a bare `&` a reference.
+        assert_eq!(
+            repair_xml("<a>A & B; C &amp;</a>").as_deref(),
+            Some("<a>A &amp; B; C &amp;</a>")
+        );
+        let bare = "&".repeat(100_000);
+        assert_eq!(
+            repair_xml(&bare).map(|repaired| repaired.len()),
+            Some(bare.len() * 5)
+        );
+    }
+
+    #[cfg(feature = "lenient")]
+    #[test]
+    fn lenient_parse_of_malformed_xml() {
+        let malformed = DEVICES.replace("Family", "Q&A\u{8}");
+        let devices = Devices::from_string(&malformed).unwrap();
+        assert_eq!(devices.0["Device"].family, "Q&A");
+    }
+
+    #[cfg(not(feature = "lenient"))]
+    #[test]
+    fn strict_parse_of_malformed_xml() {
+        let malformed = DEVICES.replace("Family", "Q&A");
+        assert!(Devices::from_string(&malformed).is_err());
+    }
+
+    /// Parses `xml` with every public entry point, returning which of them succeeded. A panic
+    /// anywhere fails the calling test.
+    fn parse_everything(xml: &str) -> Vec<bool> {
+        use crate::pack_index::{PdscRef, Pidx, Vidx};
+        use crate::pdsc::{Board, Conditions, Generator, Pack, PackInfo, Release, Releases};
+
+        let package = Pack::from_string(xml);
+        if let Ok(package) = &package {
+            package.make_components();
+            package.make_dump_devices();
+            package.devices_matching(&Default::default());
+        }
+        let devices = Devices::from_string(xml);
+        if let Ok(devices) = &devices {
+            devices.stats();
+            for device in devices.0.values() {
+                device.summary();
+                device.validate();
+                device.memories.merge_adjacent();
+                device.debug_topology();
+            }
+        }
+        vec![
+            package.is_ok(),
+            devices.is_ok(),
+            PackInfo::from_string(xml).is_ok(),
+            Conditions::from_string(xml).is_ok(),
+            Releases::from_string(xml).is_ok(),
+            Release::from_string(xml).is_ok(),
+            Generator::from_string(xml).is_ok(),
+            Board::from_string(xml).is_ok(),
+            Vidx::from_string(xml).is_ok(),
+            Pidx::from_string(xml).is_ok(),
+            PdscRef::from_string(xml).is_ok(),
+        ]
+    }
+
+    const PACKAGE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+        <package schemaVersion="1.7.28">
+          <vendor>Vendor</vendor>
+          <name>Pack</name>
+          <description>A pack</description>
+          <url>http://example.com/packs/</url>
+          <releases>
+            <release version="1.0.0">First</release>
+          </releases>
+          <conditions>
+            <condition id="Cond">
+              <require Dname="STM32*"/>
+            </condition>
+          </conditions>
+          <components>
+            <component Cclass="Device" Cgroup="Startup" Cversion="1.0.0">
+              <description>Startup</description>
+              <files/>
+            </component>
+          </components>
+          <devices>
+            <family Dfamily="Family" Dvendor="Vendor:1">
+              <processor Dcore="Cortex-M4"/>
+              <memory id="IROM1" start="0x08000000" size="0x100000" startup="1"/>
+              <device Dname="STM32F407VG" condition="Cond"/>
+            </family>
+          </devices>
+        </package>"#;
+
+    #[test]
+    fn malformed_input_is_an_error() {
+        let mut corpus: Vec<String> = vec![
+            String::new(),
+            "\u{feff}".into(),
+            "<".into(),
+            "<package>".into(),
+            "<devices><family".into(),
+            "<?xml version=\"1.0\"?>".into(),
+            "<a></b>".into(),
+            "not xml at all".into(),
+        ];
+        for len in (1..PACKAGE.len()).step_by(7) {
+            if PACKAGE.is_char_boundary(len) && !PACKAGE[len..].trim().is_empty() {
+                corpus.push(PACKAGE[..len].to_string());
+            }
+        }
+        for xml in &corpus {
+            assert!(
+                parse_everything(xml).iter().all(|ok| !ok),
+                "parsed malformed input {:?}",
+                xml
+            );
+        }
+        assert!(parse_everything(PACKAGE)[0]);
+    }
+
+    #[test]
+    fn hostile_input_does_not_panic() {
+        let nested = format!(
+            "<devices><family Dfamily=\"F\">{}{}</family></devices>",
+            "<subFamily DsubFamily=\"S\">".repeat(20_000),
+            "</subFamily>".repeat(20_000)
+        );
+        let wildcard = PACKAGE
+            .replace("STM32*", &"*".repeat(64))
+            .replace("STM32F407VG", &"A".repeat(4096));
+        let corpus = [
+            PACKAGE.replace(">First<", "><"),
+            PACKAGE.replace(
+                "<releases>\n            <release version=\"1.0.0\">First</release>\n          </releases>",
+                "",
+            ),
+            PACKAGE.replace("Cclass=\"Device\" ", ""),
+            PACKAGE.replace(
+                "<processor Dcore=\"Cortex-M4\"/>",
+                "<processor Dcore=\"Cortex-M4\"/><accessportV3 __apid=\"0\"/><debug __apid=\"0\"/>",
+            ),
+            PACKAGE.replace("0x100000", "0xFFFFFFFFFFFFFFFF"),
+            PACKAGE.replace("0x08000000", "0x1FFFFFFFFFFFFFFFF"),
+            PACKAGE.replace(
+                "<device Dname",
+                "<memory id=\"IROM2\" start=\"0x08100000\" size=\"0xFFFFFFFFFFFFFFFF\"/><device Dname",
+            ),
+            PACKAGE.replace("Dcore=\"Cortex-M4\"", "Dcore=\"\u{1F600}\" Punits=\"-1\" __dp=\"999\""),
+            PACKAGE.replace("STM32F407VG", "STM32\u{e9}\u{e9}\u{e9}\u{e9}\u{e9}"),
+            nested,
+            wildcard,
+        ];
+        for xml in &corpus {
+            parse_everything(xml);
+        }
+        let deep = |levels| {
+            format!(
+                "<devices><family Dfamily=\"F\">{}<device Dname=\"D\"/>{}</family></devices>",
+                "<subFamily DsubFamily=\"S\">".repeat(levels),
+                "</subFamily>".repeat(levels)
+            )
+        };
+        assert!(Devices::from_string(&deep(MAX_NESTING - 2)).is_ok());
+        assert!(Devices::from_string(&deep(MAX_NESTING - 1)).is_err());
+        for name in [
+            "",
+            "STM32",
+            "STM32F\u{e9}07VG",
+            "STM32F4\u{e9}\u{e9}\u{e9}\u{e9}",
+            "nRF5\u{e9}\u{e9}",
+            "nRF52832_\u{e9}",
+            "ATSAM\u{e9}\u{e9}\u{e9}\u{e9}\u{e9}\u{e9}",
+            "ATSAMD21G18A-\u{e9}",
+        ] {
+            crate::pdsc::DeviceName::parse(name);
+        }
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn from_mmap() {
+        let path =
+            std::env::temp_dir().join(format!("cmsis-pack-mmap-{}.pdsc", std::process::id()));
+        std::fs::write(&path, format!("\u{feff}{}", DEVICES)).unwrap();
+        let devices = Devices::from_mmap(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(devices.unwrap().0.contains_key("Device"));
+    }
+}
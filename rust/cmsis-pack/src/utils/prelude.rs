@@ -1,4 +1,5 @@
 pub use super::parse::{
-    assert_root_name, attr_map, attr_parse, attr_parse_hex, child_text, FromElem,
+    assert_root_name, attr_map, attr_parse, attr_parse_hex, attr_parse_size, child_text,
+    extra_attributes, Diagnostics, FromElem, LogDiagnostics, ParseOptions, ResultDiagnosticsExt,
 };
 pub use super::ResultLogExt;